@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use toml;
+
+use crate::error::Error;
+use crate::Result;
+
+/// The user-configurable settings for a subject, as loaded from its `info.toml` metadata file.
+///
+/// Kept as a raw TOML value rather than a fixed struct since subjects may carry arbitrary
+/// template/compile-command/metadata keys, which get deep-merged across a subject hierarchy
+/// (see `Subject::resolved_config`) and passed straight through as render context data.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubjectConfig(toml::Value);
+
+impl SubjectConfig {
+    /// Creates an empty configuration.
+    pub fn new() -> Self {
+        Self(toml::Value::from(HashMap::<String, toml::Value>::new()))
+    }
+
+    /// Returns the configuration as a raw TOML value.
+    pub fn as_value(&self) -> &toml::Value {
+        &self.0
+    }
+
+    /// The configured compile command template, or an empty string if unset.
+    pub fn command(&self) -> String {
+        self.0
+            .get("command")
+            .and_then(toml::Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// The configured note file globs, or an empty list if unset.
+    pub fn files(&self) -> Vec<String> {
+        self.0
+            .get("files")
+            .and_then(toml::Value::as_array)
+            .map(|globs| {
+                globs
+                    .iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Deep-merges `other` into this configuration.
+    ///
+    /// Keys present in `other` override this configuration's, except when both sides hold a
+    /// table for the same key, in which case the tables are merged recursively instead of one
+    /// replacing the other outright.
+    pub fn merge(
+        &mut self,
+        other: &Self,
+    ) -> &mut Self {
+        Self::merge_values(&mut self.0, &other.0);
+        self
+    }
+
+    fn merge_values(
+        base: &mut toml::Value,
+        other: &toml::Value,
+    ) {
+        match (base, other) {
+            (toml::Value::Table(base_table), toml::Value::Table(other_table)) => {
+                for (key, other_value) in other_table {
+                    match base_table.get_mut(key) {
+                        Some(base_value) => Self::merge_values(base_value, other_value),
+                        None => {
+                            base_table.insert(key.clone(), other_value.clone());
+                        }
+                    }
+                }
+            }
+            (base, other) => {
+                *base = other.clone();
+            }
+        }
+    }
+}
+
+impl Default for SubjectConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TryFrom<PathBuf> for SubjectConfig {
+    type Error = Error;
+
+    fn try_from(path: PathBuf) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(Error::IoError)?;
+        let value: toml::Value = toml::from_str(&contents).map_err(Error::TomlValueError)?;
+
+        Ok(Self(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_overrides_scalars_and_recurses_into_tables() {
+        let mut parent = SubjectConfig::new();
+        if let toml::Value::Table(table) = &mut parent.0 {
+            table.insert("command".to_string(), toml::Value::from("latexmk"));
+
+            let mut template = toml::Value::from(HashMap::<String, toml::Value>::new());
+            if let toml::Value::Table(template_table) = &mut template {
+                template_table.insert("default".to_string(), toml::Value::from("article.hbs"));
+            }
+            table.insert("template".to_string(), template);
+        }
+
+        let mut child = SubjectConfig::new();
+        if let toml::Value::Table(table) = &mut child.0 {
+            let mut template = toml::Value::from(HashMap::<String, toml::Value>::new());
+            if let toml::Value::Table(template_table) = &mut template {
+                template_table.insert("default".to_string(), toml::Value::from("notes.hbs"));
+            }
+            table.insert("template".to_string(), template);
+        }
+
+        parent.merge(&child);
+
+        assert_eq!(
+            parent.as_value().get("command").unwrap().as_str(),
+            Some("latexmk")
+        );
+        assert_eq!(
+            parent
+                .as_value()
+                .get("template")
+                .unwrap()
+                .get("default")
+                .unwrap()
+                .as_str(),
+            Some("notes.hbs")
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::convert::From;
+use std::error;
+use std::fmt;
+use std::io;
+use std::path;
+use std::process;
+
+use globwalk;
+use handlebars;
+use serde_json;
+
+/// An enum for errors possible to happen in the Texture Notes library.
+#[derive(Debug)]
+pub enum Error {
+    /// Error when the value is invalid in a function.
+    ValueError,
+
+    /// Error when the profile is not valid or does not exists
+    InvalidProfileError(path::PathBuf),
+
+    /// Error when the subject does not exist at the given path in the shelf.
+    InvalidSubjectError(path::PathBuf),
+
+    /// Error when a subject lookup fails, carrying the closest-matching existing subjects
+    /// (ranked by edit distance) as suggestions.
+    UnknownSubjectError(path::PathBuf, Vec<String>),
+
+    /// Used when the shelf is not yet exported while attempting to do some filesystem operations.
+    UnexportedShelfError(path::PathBuf),
+
+    /// Used when the associated subject is missing in the shelf database.
+    DanglingSubjectError(path::PathBuf),
+
+    /// IO-related errors mainly given by the official standard library IO library.
+    IoError(io::Error),
+
+    /// Given when a shell process has gone something wrong.
+    ProcessError(process::ExitStatus),
+
+    /// Error when a part of the profile data is missing.
+    MissingDataError(String),
+
+    /// Related errors for Serde.
+    SerdeValueError(serde_json::Error),
+
+    /// Related errors for parsing TOML values.
+    TomlValueError(toml::de::Error),
+
+    /// Related errors for globbing file patterns.
+    GlobParsingError(globwalk::GlobError),
+
+    /// Related errors for Handlebars.
+    HandlebarsTemplateError(handlebars::TemplateError),
+    HandlebarsTemplateFileError(handlebars::TemplateFileError),
+    HandlebarsRenderError(handlebars::RenderError),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match *self {
+            Error::ValueError => write!(f, "Given value is not valid."),
+            Error::InvalidProfileError(ref path) => {
+                write!(f, "Profile at '{}' is not valid.", path.to_string_lossy())
+            }
+            Error::InvalidSubjectError(ref path) => {
+                write!(f, "The subject at '{}' does not exist.", path.to_string_lossy())
+            }
+            Error::UnknownSubjectError(ref path, ref suggestions) => {
+                if suggestions.is_empty() {
+                    write!(f, "The subject at '{}' does not exist.", path.to_string_lossy())
+                } else {
+                    write!(
+                        f,
+                        "The subject at '{}' does not exist. Did you mean: {}?",
+                        path.to_string_lossy(),
+                        suggestions.join(", ")
+                    )
+                }
+            }
+            Error::UnexportedShelfError(ref path) => write!(
+                f,
+                "The shelf at path '{}' is not yet exported in the filesystem.",
+                path.to_string_lossy()
+            ),
+            Error::DanglingSubjectError(ref path) => {
+                write!(f, "The subject at path '{}' is missing", path.to_string_lossy())
+            }
+            Error::IoError(ref err) => err.fmt(f),
+            Error::ProcessError(ref _exit) => write!(f, "The process is not successful."),
+            Error::MissingDataError(ref p) => write!(f, "{} is missing.", p),
+            Error::SerdeValueError(ref p) => write!(f, "{} is invalid.", p),
+            Error::TomlValueError(ref p) => write!(f, "{} is invalid.", p),
+            Error::GlobParsingError(ref p) => write!(f, "{} is an invalid glob pattern.", p),
+            Error::HandlebarsTemplateError(ref p) => write!(f, "{} is an invalid template.", p),
+            Error::HandlebarsTemplateFileError(ref p) => {
+                write!(f, "Handlebars with the instance '{}' has an error occurred.", p)
+            }
+            Error::HandlebarsRenderError(ref p) => write!(f, "{}: Error occurred while rendering.", p),
+        }
+    }
+}
@@ -130,7 +130,15 @@ pub fn relative_path_from<P: AsRef<Path>, Q: AsRef<Path>>(
 
                 // if the base path has more components
                 (None, _) => common_components.push(path::Component::ParentDir),
-                (Some(a), Some(b)) if common_components.is_empty() && a == b => (),
+                (Some(a), Some(b)) if common_components.is_empty() && components_equivalent(&a, &b) => (),
+                // a genuinely different prefix (different drive, or UNC share vs local) can never
+                // be made relative to the other
+                (Some(a), Some(b))
+                    if common_components.is_empty()
+                        && (is_prefix_component(&a) || is_prefix_component(&b)) =>
+                {
+                    return None;
+                }
                 (Some(a), Some(b)) if b == path::Component::CurDir => common_components.push(a),
                 (Some(_), Some(b)) if b == path::Component::ParentDir => return None,
                 (Some(a), Some(_)) => {
@@ -149,48 +157,258 @@ pub fn relative_path_from<P: AsRef<Path>, Q: AsRef<Path>>(
     }
 }
 
+/// Compares two path components for equivalence, for use when checking whether the leading
+/// components of two paths refer to the same root rather than requiring bit-for-bit equality.
+///
+/// A `Prefix::VerbatimDisk`/`VerbatimUNC` prefix compares equal to its non-verbatim counterpart
+/// (`\\?\C:\` vs `C:\`), and a `Prefix::Disk` drive letter compares case-insensitively (`c:` vs
+/// `C:`); the rest of the path stays case-sensitive, and every other component compares exactly
+/// as `==` would.
+fn components_equivalent(
+    a: &Component,
+    b: &Component,
+) -> bool {
+    match (a, b) {
+        (Component::Prefix(a), Component::Prefix(b)) => {
+            normalize_prefix(a.kind()) == normalize_prefix(b.kind())
+        }
+        _ => a == b,
+    }
+}
+
+/// Canonicalizes a Windows path prefix to its non-verbatim form, and a disk drive letter to
+/// uppercase, so `components_equivalent` can compare them directly.
+fn normalize_prefix(prefix: path::Prefix) -> path::Prefix {
+    match prefix {
+        path::Prefix::VerbatimDisk(disk) => path::Prefix::Disk(disk.to_ascii_uppercase()),
+        path::Prefix::Disk(disk) => path::Prefix::Disk(disk.to_ascii_uppercase()),
+        path::Prefix::VerbatimUNC(server, share) => path::Prefix::UNC(server, share),
+        other => other,
+    }
+}
+
+fn is_prefix_component(component: &Component) -> bool {
+    matches!(component, Component::Prefix(_))
+}
+
+/// Turns a relative `path` into an absolute one by joining it onto `base` and folding `.`/`..`
+/// lexically, without touching the filesystem.
+///
+/// Unlike `std::fs::canonicalize`, `path` (and `base`) don't need to exist and symlinks are never
+/// resolved; components are folded purely by string manipulation via `naively_normalize_path`. If
+/// `path` is already absolute, it is normalized as-is and `base` is ignored.
+pub fn absolutize<P: AsRef<Path>, Q: AsRef<Path>>(
+    path: P,
+    base: Q,
+) -> PathBuf {
+    let path = path.as_ref();
+
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.as_ref().join(path)
+    };
+
+    naively_normalize_path(joined).unwrap_or_default()
+}
+
+/// Expands a leading `~`/`~<user>` into the relevant home directory and any `$VAR`/`${VAR}`
+/// segment into the corresponding environment variable, then normalizes the result.
+///
+/// Only a leading `~` is treated as a home-directory reference; one appearing anywhere else in
+/// the path is left as a literal character. If the relevant home directory can't be determined
+/// (no `$HOME`, or an unrecognized user), the leading `~` segment is left untouched rather than
+/// dropped. This never touches the filesystem beyond reading `/etc/passwd` to resolve `~<user>`
+/// on Unix.
+pub fn expand_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path_str = path.as_ref().to_string_lossy();
+
+    let (tilde_segment, rest) = match path_str.find(['/', '\\']) {
+        Some(index) => (&path_str[..index], &path_str[index..]),
+        None => (path_str.as_ref(), ""),
+    };
+
+    let expanded = if let Some(user) = tilde_segment.strip_prefix('~') {
+        match home_dir(user) {
+            Some(home) => format!("{}{}", home.to_string_lossy(), rest),
+            None => path_str.to_string(),
+        }
+    } else {
+        path_str.to_string()
+    };
+
+    naively_normalize_path(expand_env_vars(&expanded)).unwrap_or_default()
+}
+
+/// Returns the home directory of `user`, or of the current user if `user` is empty.
+#[cfg(target_family = "unix")]
+fn home_dir(user: &str) -> Option<PathBuf> {
+    if user.is_empty() {
+        return std::env::var_os("HOME").map(PathBuf::from);
+    }
+
+    let passwd = fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? != user {
+            return None;
+        }
+
+        fields.nth(4).map(PathBuf::from)
+    })
+}
+
+/// Returns the home directory of `user`, or of the current user if `user` is empty.
+///
+/// Resolving another user's home directory isn't supported on Windows here.
+#[cfg(target_family = "windows")]
+fn home_dir(user: &str) -> Option<PathBuf> {
+    if user.is_empty() {
+        return std::env::var_os("USERPROFILE").map(PathBuf::from);
+    }
+
+    None
+}
+
+/// Expands `$VAR` and `${VAR}` references in `s` from the environment, leaving unrecognized or
+/// unset variables as-is rather than substituting an empty string.
+fn expand_env_vars(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                name.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        let closed = !braced || chars.peek() == Some(&'}');
+        if braced && closed {
+            chars.next();
+        }
+
+        if name.is_empty() || (braced && !closed) {
+            result.push('$');
+            if braced {
+                result.push('{');
+            }
+            result.push_str(&name);
+            if braced && closed {
+                result.push('}');
+            }
+            continue;
+        }
+
+        match std::env::var(&name) {
+            Ok(value) => result.push_str(&value),
+            Err(_) => {
+                result.push('$');
+                if braced {
+                    result.push('{');
+                    result.push_str(&name);
+                    result.push('}');
+                } else {
+                    result.push_str(&name);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Rewrites every path component that consists entirely of dots with length 3 or more (e.g.
+/// `...`, `....`) into one fewer `../` hops than it has dots (`...` becomes `../..`), the
+/// shorthand for "go up multiple levels" some users
+/// expect from their shell. A component containing any non-dot byte, like the hidden file
+/// `.bashrc` or a subject literally named `...foo`, is left untouched, as are ordinary `.`/`..`
+/// components, which already carry their normal meaning.
+///
+/// This is meant to run as a pre-pass before `naively_normalize_path`'s own parent-dir folding, so
+/// that e.g. `.../sub` still collapses against preceding components correctly. It inspects each
+/// component's raw bytes rather than assuming valid UTF-8, so a non-Unicode component is left
+/// alone rather than mangled or panicking.
+pub fn expand_ndots<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let mut expanded = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(os_str) => {
+                let bytes = os_str.as_encoded_bytes();
+
+                if bytes.len() >= 3 && bytes.iter().all(|&b| b == b'.') {
+                    for _ in 0..bytes.len() - 1 {
+                        expanded.push("..");
+                    }
+                } else {
+                    expanded.push(os_str);
+                }
+            }
+            _ => expanded.push(component.as_os_str()),
+        }
+    }
+
+    expanded
+}
+
 /// Normalize the given path.
 /// Unlike the standard library `std::fs::canonicalize` function, it does not need the file to be in the filesystem.
 ///
 /// That said, this leaves compromise the implementation to be very naive.
 /// All resulting path will be based on the current directory.
 ///
-/// If the resulting normalized path is empty, it will return `None`.
+/// If the resulting normalized path is empty, it will return `None`. This never performs a lossy
+/// string conversion, so a path with non-UTF-8 components round-trips unchanged.
 pub fn naively_normalize_path<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
-    let path = path.as_ref();
+    let path = expand_ndots(path.as_ref());
 
     let mut normalized_components = vec![];
 
     for component in path.components() {
         match &component {
             Component::CurDir => continue,
-            // The condition below can be safe to execute.
-            // It will immediately continue to the if block if one of them is true which is why
-            // the ordering of the conditions is important.
-            // If the vector is empty, it will never reach the second condition.
-            // That said, there has to be a better way than this.
-            Component::ParentDir => match normalized_components.is_empty()
-                || is_parent_dir(normalized_components[normalized_components.len() - 1])
-            {
-                true => normalized_components.push(component),
-                false => {
+            Component::ParentDir => match normalized_components.last() {
+                None => normalized_components.push(component),
+                // Already at the filesystem root (or a Windows prefix); ".." can't climb any
+                // higher, so it's discarded instead of popping the root component itself, which
+                // would otherwise leave the path looking relative (or empty) when it's meant to
+                // stay clamped at the root.
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => (),
+                Some(&last) if is_parent_dir(last) => normalized_components.push(component),
+                Some(_) => {
                     normalized_components.pop();
-                    ()
                 }
             },
             _ => normalized_components.push(component),
         }
     }
 
+    if normalized_components.is_empty() {
+        return None;
+    }
+
     let mut normalized_path = PathBuf::new();
     for component in normalized_components {
         normalized_path.push(component.as_os_str());
     }
 
-    match normalized_path.to_string_lossy().is_empty() {
-        true => None,
-        false => Some(normalized_path),
-    }
+    Some(normalized_path)
 }
 
 fn is_parent_dir(component: Component) -> bool {
@@ -303,6 +521,33 @@ mod tests {
         );
     }
 
+    #[cfg(windows)]
+    #[test]
+    fn relpath_treats_verbatim_and_plain_drive_prefixes_as_the_same_root() {
+        let base = PathBuf::from(r"\\?\C:\dev\sda\calculus-drive");
+        let dst = PathBuf::from(r"C:\dev\sda\common");
+
+        assert_eq!(relative_path_from(dst, base), Some("../common".into()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn relpath_treats_drive_letters_case_insensitively() {
+        let base = PathBuf::from(r"c:\dev\sda\calculus-drive");
+        let dst = PathBuf::from(r"C:\dev\sda\common");
+
+        assert_eq!(relative_path_from(dst, base), Some("../common".into()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn relpath_between_different_drives_is_none() {
+        let base = PathBuf::from(r"C:\dev\sda\calculus-drive");
+        let dst = PathBuf::from(r"D:\dev\sda\common");
+
+        assert_eq!(relative_path_from(dst, base), None);
+    }
+
     #[test]
     fn leading_current_dir_naive_normalized() {
         let test_case = PathBuf::from("./tests/lanoma-profile/notes/calculus");
@@ -355,4 +600,154 @@ mod tests {
 
         assert_eq!(naively_normalize_path(test_case), Some("../p".into()));
     }
+
+    #[test]
+    fn triple_dot_expands_to_two_parent_dirs() {
+        let test_case = PathBuf::from(".../sub");
+
+        assert_eq!(naively_normalize_path(test_case), Some("../../sub".into()));
+    }
+
+    #[test]
+    fn quadruple_dot_expands_to_three_parent_dirs() {
+        let test_case = PathBuf::from("..../sub");
+
+        assert_eq!(
+            naively_normalize_path(test_case),
+            Some("../../../sub".into())
+        );
+    }
+
+    #[test]
+    fn ndots_collapse_against_preceding_components() {
+        let test_case = PathBuf::from("tests/lanoma-profile/.../common");
+
+        assert_eq!(naively_normalize_path(test_case), Some("common".into()));
+    }
+
+    #[test]
+    fn dotted_component_with_other_characters_is_untouched() {
+        let test_case = PathBuf::from("subjects/...foo/notes");
+
+        assert_eq!(
+            naively_normalize_path(test_case),
+            Some("subjects/...foo/notes".into())
+        );
+    }
+
+    #[test]
+    fn single_and_double_dot_components_keep_normal_meaning() {
+        let test_case = PathBuf::from("./Calculus/..");
+
+        assert_eq!(naively_normalize_path(test_case), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parent_dir_above_the_root_clamps_instead_of_popping_it() {
+        let test_case = PathBuf::from("/../../escaping");
+
+        assert_eq!(naively_normalize_path(test_case), Some("/escaping".into()));
+    }
+
+    #[test]
+    fn tilde_expands_to_the_current_user_home_directory() {
+        let home = PathBuf::from(std::env::var_os("HOME").unwrap());
+
+        assert_eq!(
+            expand_path("~/notes/calculus"),
+            home.join("notes/calculus")
+        );
+    }
+
+    #[test]
+    fn tilde_in_the_middle_of_a_component_is_literal() {
+        assert_eq!(
+            expand_path("notes/~backup/calculus"),
+            PathBuf::from("notes/~backup/calculus")
+        );
+    }
+
+    #[test]
+    fn dollar_var_and_braced_var_are_both_expanded() {
+        std::env::set_var("LANOMA_TEST_NOTES_ROOT", "/mnt/notes");
+
+        assert_eq!(
+            expand_path("$LANOMA_TEST_NOTES_ROOT/physics"),
+            PathBuf::from("/mnt/notes/physics")
+        );
+        assert_eq!(
+            expand_path("${LANOMA_TEST_NOTES_ROOT}/physics"),
+            PathBuf::from("/mnt/notes/physics")
+        );
+
+        std::env::remove_var("LANOMA_TEST_NOTES_ROOT");
+    }
+
+    #[test]
+    fn unset_var_is_left_untouched() {
+        std::env::remove_var("LANOMA_TEST_UNSET_VAR");
+
+        assert_eq!(
+            expand_path("$LANOMA_TEST_UNSET_VAR/physics"),
+            PathBuf::from("$LANOMA_TEST_UNSET_VAR/physics")
+        );
+    }
+
+    #[test]
+    fn expand_path_composes_with_ndots_and_normalization() {
+        std::env::set_var("LANOMA_TEST_NESTED_ROOT", "tests/lanoma-profile/notes/calculus");
+
+        assert_eq!(
+            expand_path("$LANOMA_TEST_NESTED_ROOT/.../common"),
+            PathBuf::from("tests/lanoma-profile/common")
+        );
+
+        std::env::remove_var("LANOMA_TEST_NESTED_ROOT");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn absolutize_joins_a_relative_path_onto_base_and_folds_it() {
+        let base = PathBuf::from("/shelf/root");
+
+        assert_eq!(
+            absolutize("subjects/../common", &base),
+            PathBuf::from("/shelf/common")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn absolutize_ignores_base_for_an_already_absolute_path() {
+        let base = PathBuf::from("/shelf/root");
+
+        assert_eq!(
+            absolutize("/elsewhere/common", &base),
+            PathBuf::from("/elsewhere/common")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn absolutize_clamps_a_path_that_escapes_above_the_root() {
+        let base = PathBuf::from("/");
+
+        assert_eq!(absolutize("../../escaping", &base), PathBuf::from("/escaping"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_components_round_trip_unchanged() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let non_utf8_name = OsStr::from_bytes(&[0x66, 0x6f, 0xff, 0x6f]);
+        let test_case = Path::new("notes").join(non_utf8_name);
+
+        assert_eq!(
+            naively_normalize_path(&test_case),
+            Some(test_case)
+        );
+    }
 }
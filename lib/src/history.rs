@@ -0,0 +1,414 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::note::Note;
+use crate::shelf::{Shelf, ShelfItem};
+use crate::subjects::Subject;
+use crate::Result;
+
+/// The shelf-relative directory under which every subject/note's history records live.
+const HISTORY_DIR: &str = ".lanoma/history";
+
+/// The name of the file tracking the current head record id(s) for a note's history.
+const HEADS_FILE: &str = "HEADS";
+
+/// The author, timestamp, and parent links of a single history record.
+///
+/// Together with the record's content, hashing the author and parents (but not the timestamp)
+/// produces the record's id, so two shelves that recorded the same change end up with the same
+/// record and can be merged without conflict.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordMetadata {
+    pub author: String,
+    pub timestamp: u64,
+    pub parents: Vec<String>,
+}
+
+/// An immutable, content-addressable snapshot of a note at one point in its history.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub id: String,
+    pub metadata: RecordMetadata,
+    pub content: Vec<u8>,
+}
+
+fn records_root(
+    shelf: &Shelf,
+    subject: &Subject,
+    note: &Note,
+) -> PathBuf {
+    let mut path = shelf.path();
+    path.push(HISTORY_DIR);
+    path.push(subject.path());
+    path.push(note.file_name());
+
+    path
+}
+
+fn heads_path(records_root: &Path) -> PathBuf {
+    records_root.join(HEADS_FILE)
+}
+
+fn read_heads(records_root: &Path) -> Vec<String> {
+    fs::read_to_string(heads_path(records_root))
+        .map(|contents| {
+            contents
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn write_heads(
+    records_root: &Path,
+    heads: &[String],
+) -> Result<()> {
+    fs::write(heads_path(records_root), heads.join("\n")).map_err(Error::IoError)
+}
+
+/// Hashes the record's content and identity-affecting metadata (author, parents) into its id.
+///
+/// `timestamp` is deliberately excluded: it records *when* a change was recorded, not *what*
+/// changed, and folding it in would mean two real invocations recording the same content never
+/// dedupe against each other since they'd essentially never land in the same clock tick.
+fn compute_record_id(
+    content: &[u8],
+    metadata: &RecordMetadata,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    hasher.update(metadata.author.as_bytes());
+    for parent in &metadata.parents {
+        hasher.update(parent.as_bytes());
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+fn read_record(
+    records_root: &Path,
+    id: &str,
+) -> Result<Record> {
+    let record_path = records_root.join(id);
+
+    let content = fs::read(record_path.join("content")).map_err(Error::IoError)?;
+    let metadata_string =
+        fs::read_to_string(record_path.join("metadata.toml")).map_err(Error::IoError)?;
+    let metadata: RecordMetadata =
+        toml::from_str(&metadata_string).map_err(Error::TomlValueError)?;
+
+    Ok(Record {
+        id: id.to_string(),
+        metadata,
+        content,
+    })
+}
+
+impl Subject {
+    /// Appends an immutable record of `note`'s current on-disk content to the shelf's history,
+    /// linking it to the note's current head record(s) as parents.
+    ///
+    /// Because the record's id is the hash of its content and metadata, recording the same
+    /// change twice (e.g. after a no-op sync) simply resolves to the existing record.
+    pub fn record_note_change(
+        &self,
+        note: &Note,
+        shelf: &Shelf,
+        author: &str,
+    ) -> Result<Record> {
+        let content = fs::read(note.path_in_shelf((self, shelf))).map_err(Error::IoError)?;
+        let root = records_root(shelf, self, note);
+        fs::create_dir_all(&root).map_err(Error::IoError)?;
+
+        let parents = read_heads(&root);
+
+        // Recording unchanged content on top of a single head that already holds it resolves
+        // to that head instead of appending a new record.
+        if let [head] = parents.as_slice() {
+            if let Ok(existing) = read_record(&root, head) {
+                if existing.content == content && existing.metadata.author == author {
+                    return Ok(existing);
+                }
+            }
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let metadata = RecordMetadata {
+            author: author.to_string(),
+            timestamp,
+            parents: parents.clone(),
+        };
+        let id = compute_record_id(&content, &metadata);
+
+        let record_path = root.join(&id);
+        if !record_path.is_dir() {
+            fs::create_dir_all(&record_path).map_err(Error::IoError)?;
+            fs::write(record_path.join("content"), &content).map_err(Error::IoError)?;
+            fs::write(
+                record_path.join("metadata.toml"),
+                toml::to_string_pretty(&metadata).unwrap(),
+            )
+            .map_err(Error::IoError)?;
+        }
+
+        write_heads(&root, &[id.clone()])?;
+
+        Ok(Record {
+            id,
+            metadata,
+            content,
+        })
+    }
+
+    /// Returns `note`'s history, starting at its current head record(s) and following parent
+    /// links back to the roots. The result is topologically ordered, newest first: a record
+    /// never appears before any of its descendants, even across the multi-parent histories
+    /// `Shelf::merge` produces.
+    pub fn note_history(
+        &self,
+        note: &Note,
+        shelf: &Shelf,
+    ) -> Result<Vec<Record>> {
+        let root = records_root(shelf, self, note);
+        let heads = read_heads(&root);
+
+        // First, discover every reachable record and count its "children" (the records that
+        // name it as a parent), so the Kahn's-algorithm pass below knows when a record has had
+        // all of its descendants emitted and is safe to emit itself.
+        let mut records = HashMap::new();
+        let mut remaining_children: HashMap<String, usize> = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut to_visit = heads.clone();
+
+        while let Some(id) = to_visit.pop() {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+
+            let record = read_record(&root, &id)?;
+            remaining_children.entry(id.clone()).or_insert(0);
+
+            for parent in &record.metadata.parents {
+                *remaining_children.entry(parent.clone()).or_insert(0) += 1;
+                to_visit.push(parent.clone());
+            }
+
+            records.insert(id, record);
+        }
+
+        // A record with zero remaining children has had every descendant emitted already (or,
+        // for the heads, has no descendants at all), so it's safe to emit next.
+        let mut ready: VecDeque<String> = heads.into_iter().collect();
+        let mut ordered = vec![];
+
+        while let Some(id) = ready.pop_front() {
+            let record = match records.remove(&id) {
+                Some(record) => record,
+                None => continue,
+            };
+
+            for parent in &record.metadata.parents {
+                if let Some(count) = remaining_children.get_mut(parent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push_back(parent.clone());
+                    }
+                }
+            }
+
+            ordered.push(record);
+        }
+
+        Ok(ordered)
+    }
+}
+
+impl Shelf {
+    /// Ingests another shelf's history records into this one.
+    ///
+    /// Records are append-only and keyed by content hash, so merging is simply copying over any
+    /// record directories this shelf doesn't already have, then unioning the two shelves' head
+    /// pointers for every note so a later `note_history` call sees both lineages.
+    pub fn merge(
+        &self,
+        other: &Shelf,
+    ) -> Result<usize> {
+        let mut self_history = self.path();
+        self_history.push(HISTORY_DIR);
+        let mut other_history = other.path();
+        other_history.push(HISTORY_DIR);
+
+        if !other_history.is_dir() {
+            return Ok(0);
+        }
+
+        fs::create_dir_all(&self_history).map_err(Error::IoError)?;
+
+        let mut copied = 0;
+        copy_new_records(&other_history, &self_history, &mut copied)?;
+
+        Ok(copied)
+    }
+}
+
+/// Recursively copies record directories from `src` into `dst` that don't already exist there,
+/// merging `HEADS` files (by set union of their lines) instead of overwriting them.
+fn copy_new_records(
+    src: &Path,
+    dst: &Path,
+    copied: &mut usize,
+) -> Result<()> {
+    for entry in fs::read_dir(src).map_err(Error::IoError)? {
+        let entry = entry.map_err(Error::IoError)?;
+        let file_type = entry.file_type().map_err(Error::IoError)?;
+        let dst_entry = dst.join(entry.file_name());
+
+        if file_type.is_dir() {
+            fs::create_dir_all(&dst_entry).map_err(Error::IoError)?;
+            copy_new_records(&entry.path(), &dst_entry, copied)?;
+        } else if entry.file_name() == HEADS_FILE {
+            let mut heads: HashSet<String> = read_heads(dst).into_iter().collect();
+            heads.extend(read_heads(src));
+
+            let mut heads: Vec<String> = heads.into_iter().collect();
+            heads.sort();
+            write_heads(dst, &heads)?;
+        } else if !dst_entry.is_file() {
+            fs::copy(entry.path(), &dst_entry).map_err(Error::IoError)?;
+            *copied += 1;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile;
+
+    fn write_note_content(
+        shelf: &Shelf,
+        subject: &Subject,
+        note: &Note,
+        contents: &str,
+    ) {
+        let path = note.path_in_shelf((subject, shelf));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn recording_the_same_change_twice_resolves_to_the_existing_record() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let shelf = Shelf::from(tmp_dir.path()).unwrap();
+        let subject = Subject::new("Calculus");
+        subject.export(&shelf).unwrap();
+
+        let note = Note::new("homework-1");
+        write_note_content(&shelf, &subject, &note, "one");
+
+        let first = subject.record_note_change(&note, &shelf, "alice").unwrap();
+        let second = subject.record_note_change(&note, &shelf, "alice").unwrap();
+
+        assert_eq!(first.id, second.id);
+
+        let history = subject.note_history(&note, &shelf).unwrap();
+        assert_eq!(history.len(), 1);
+    }
+
+    #[test]
+    fn note_history_follows_parent_links_newest_first() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let shelf = Shelf::from(tmp_dir.path()).unwrap();
+        let subject = Subject::new("Calculus");
+        subject.export(&shelf).unwrap();
+
+        let note = Note::new("homework-1");
+
+        write_note_content(&shelf, &subject, &note, "one");
+        let first = subject.record_note_change(&note, &shelf, "alice").unwrap();
+
+        write_note_content(&shelf, &subject, &note, "two");
+        let second = subject.record_note_change(&note, &shelf, "alice").unwrap();
+
+        let history = subject.note_history(&note, &shelf).unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].id, second.id);
+        assert_eq!(history[1].id, first.id);
+    }
+
+    #[test]
+    fn merge_copies_records_and_unions_heads_across_shelves() {
+        let tmp_dir_a = tempfile::TempDir::new().unwrap();
+        let shelf_a = Shelf::from(tmp_dir_a.path()).unwrap();
+        let subject = Subject::new("Calculus");
+        subject.export(&shelf_a).unwrap();
+
+        let note = Note::new("homework-1");
+        write_note_content(&shelf_a, &subject, &note, "one");
+        subject.record_note_change(&note, &shelf_a, "alice").unwrap();
+
+        let tmp_dir_b = tempfile::TempDir::new().unwrap();
+        let shelf_b = Shelf::from(tmp_dir_b.path()).unwrap();
+        subject.export(&shelf_b).unwrap();
+        write_note_content(&shelf_b, &subject, &note, "two");
+        subject.record_note_change(&note, &shelf_b, "bob").unwrap();
+
+        let copied = shelf_b.merge(&shelf_a).unwrap();
+        assert!(copied > 0);
+
+        let history = subject.note_history(&note, &shelf_b).unwrap();
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn note_history_orders_descendants_before_a_shared_ancestor_in_a_diamond() {
+        let tmp_dir_a = tempfile::TempDir::new().unwrap();
+        let shelf_a = Shelf::from(tmp_dir_a.path()).unwrap();
+        let subject = Subject::new("Calculus");
+        subject.export(&shelf_a).unwrap();
+
+        let note = Note::new("homework-1");
+        write_note_content(&shelf_a, &subject, &note, "base");
+        let a = subject.record_note_change(&note, &shelf_a, "alice").unwrap();
+
+        let tmp_dir_b = tempfile::TempDir::new().unwrap();
+        let shelf_b = Shelf::from(tmp_dir_b.path()).unwrap();
+        subject.export(&shelf_b).unwrap();
+        shelf_b.merge(&shelf_a).unwrap();
+
+        write_note_content(&shelf_b, &subject, &note, "c");
+        let c = subject.record_note_change(&note, &shelf_b, "bob").unwrap();
+
+        write_note_content(&shelf_a, &subject, &note, "b");
+        let b = subject.record_note_change(&note, &shelf_a, "alice").unwrap();
+
+        shelf_a.merge(&shelf_b).unwrap();
+
+        write_note_content(&shelf_a, &subject, &note, "d");
+        let d = subject.record_note_change(&note, &shelf_a, "alice").unwrap();
+
+        let history = subject.note_history(&note, &shelf_a).unwrap();
+        let ids: Vec<&str> = history.iter().map(|record| record.id.as_str()).collect();
+
+        assert_eq!(ids.len(), 4);
+        assert_eq!(ids[0], d.id);
+        assert_eq!(ids[3], a.id);
+        assert!(ids[1..3].contains(&b.id.as_str()));
+        assert!(ids[1..3].contains(&c.id.as_str()));
+    }
+}
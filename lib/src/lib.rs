@@ -0,0 +1,43 @@
+use toml;
+
+pub mod config;
+pub mod error;
+pub mod helpers;
+pub mod history;
+pub mod shelf;
+pub mod subjects;
+
+use crate::error::Error;
+
+/// The crate-wide result type, defaulting the error branch to the library's own `Error` enum.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Something that can be represented as a TOML value, mainly for use as Handlebars render
+/// context data.
+pub trait Object {
+    fn data(&self) -> toml::Value;
+}
+
+/// Inserts the given key-value pairs into a TOML table, overwriting any existing keys.
+#[macro_export]
+macro_rules! upsert_toml_table {
+    ($table:expr, $(($key:expr, $value:expr)),* $(,)?) => {
+        if let toml::Value::Table(ref mut table) = $table {
+            $(
+                table.insert($key.to_string(), toml::Value::try_from($value).unwrap());
+            )*
+        }
+    };
+}
+
+/// Inserts the given key-value pairs into a TOML table, always overwriting existing keys.
+///
+/// This differs from `upsert_toml_table!` only in name, kept for readability at call sites that
+/// are explicitly replacing derived/computed fields (e.g. `_path`, `_full_name`) rather than
+/// user-provided ones.
+#[macro_export]
+macro_rules! modify_toml_table {
+    ($table:expr, $(($key:expr, $value:expr)),* $(,)?) => {
+        $crate::upsert_toml_table!($table, $(($key, $value)),*);
+    };
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+use std::fs::{self, DirBuilder};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::helpers;
+use crate::note::Note;
+use crate::subjects::Subject;
+use crate::Result;
+
+/// A struct holding the common export options.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    strict: bool,
+}
+
+impl ExportOptions {
+    /// Creates a new instance of the export options.
+    /// By default, all of the options are set to false.
+    pub fn new() -> Self {
+        Self { strict: false }
+    }
+
+    /// Sets the strictness of the export.
+    /// If set, exporting an item that already exists on disk will cause an error.
+    pub fn strict(
+        &mut self,
+        strict: bool,
+    ) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+}
+
+/// The shelf is where it contains the subjects and its notes.
+/// In other words, it is the base directory of the operations taken place in Texture Notes.
+#[derive(Debug, Clone)]
+pub struct Shelf {
+    path: PathBuf,
+}
+
+impl Shelf {
+    /// Create a new shelf instance.
+    ///
+    /// The path is expanded (`~`/`~user`, `$VAR`/`${VAR}`) and normalized, so a profile can store
+    /// a shelf location like `~/notes` or `$NOTES_ROOT/physics` and have it resolve consistently.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: helpers::fs::expand_path(path),
+        }
+    }
+
+    /// Creates a shelf instance from the filesystem.
+    pub fn from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let shelf = Self::new(path);
+
+        if !shelf.is_valid() {
+            return Err(Error::ValueError);
+        }
+
+        Ok(shelf)
+    }
+
+    /// Returns the current path of the shelf.
+    pub fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    /// Resolves `path` into an absolute location against this shelf's own path, lexically folding
+    /// `.`/`..` without requiring `path` to exist or touching the filesystem.
+    ///
+    /// This complements `helpers::fs::relative_path_from`, which instead turns an absolute
+    /// location back into one relative to the shelf, and lets callers building note/subject trees
+    /// compute stable absolute locations before anything is exported.
+    pub fn resolve<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> PathBuf {
+        helpers::fs::absolutize(path, self.path())
+    }
+
+    /// Checks if the shelf is valid.
+    pub fn is_valid(&self) -> bool {
+        self.path.is_dir()
+    }
+
+    /// Exports the shelf in the filesystem.
+    pub fn export(&mut self) -> Result<()> {
+        let dir_builder = DirBuilder::new();
+
+        if !self.is_valid() {
+            helpers::fs::create_folder(&dir_builder, self.path())?;
+        }
+
+        Ok(())
+    }
+
+    /// Exports `item` (if it isn't already) and creates a relative symlink to it at `dst`, so the
+    /// same subject or note can appear under more than one location in the shelf without
+    /// duplicating its contents.
+    ///
+    /// See `ShelfItem::link`, which this delegates to.
+    pub fn link_item<T: ShelfItem<&Shelf>>(
+        &self,
+        item: &T,
+        dst: &Path,
+    ) -> Result<()> {
+        item.link(self, dst)
+    }
+
+    /// Creates a fresh, empty query index for this shelf.
+    ///
+    /// The index is kept separate from the shelf itself so callers that only need a one-off
+    /// listing aren't forced to pay for a cache; code that repeatedly lists subjects/notes (the
+    /// CLI, exporters) should keep one `Index` alive across those calls instead.
+    pub fn index(&self) -> Index {
+        Index::new()
+    }
+}
+
+/// A memoized, filesystem-backed query cache over a shelf's subjects and notes.
+///
+/// Modeled on incremental-computation frameworks: a scanned subject directory is an "input"
+/// keyed by its last-observed modification time, and the note listing it produces is a
+/// "derived query" that is memoized and only recomputed when that input changes.
+pub struct Index {
+    dirs: HashMap<PathBuf, DirEntryCache>,
+    revision: u64,
+}
+
+struct DirEntryCache {
+    mtime: Option<SystemTime>,
+    notes: Vec<Note>,
+    revision: u64,
+}
+
+impl Index {
+    /// Creates a new, empty index with no cached entries.
+    pub fn new() -> Self {
+        Self {
+            dirs: HashMap::new(),
+            revision: 0,
+        }
+    }
+
+    /// The current revision of the index, bumped every time a directory is (re)scanned.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// The revision at which `subject`'s directory was last scanned, or `None` if it has never
+    /// been queried through this index.
+    pub fn revision_of(
+        &self,
+        subject: &Subject,
+        shelf: &Shelf,
+    ) -> Option<u64> {
+        self.dirs
+            .get(&subject.path_in_shelf(&shelf))
+            .map(|entry| entry.revision)
+    }
+
+    /// Returns the notes under `subject`, matching `file_globs`, re-walking the filesystem only
+    /// if the subject's directory modification time has changed since the last query.
+    pub fn notes_for_subject(
+        &mut self,
+        subject: &Subject,
+        file_globs: &Vec<String>,
+        shelf: &Shelf,
+    ) -> Result<&Vec<Note>> {
+        let dir = subject.path_in_shelf(&shelf);
+        let mtime = fs::metadata(&dir).and_then(|metadata| metadata.modified()).ok();
+
+        let is_stale = match self.dirs.get(&dir) {
+            Some(cached) => cached.mtime != mtime,
+            None => true,
+        };
+
+        if is_stale {
+            let notes = subject.get_notes_in_fs(file_globs, &shelf)?;
+            self.revision += 1;
+
+            self.dirs.insert(
+                dir.clone(),
+                DirEntryCache {
+                    mtime,
+                    notes,
+                    revision: self.revision,
+                },
+            );
+        }
+
+        Ok(&self.dirs[&dir].notes)
+    }
+}
+
+/// A trait implementing the shelf operations common to subjects and notes.
+pub trait ShelfItem<S> {
+    fn path_in_shelf(
+        &self,
+        params: S,
+    ) -> PathBuf;
+
+    fn is_item_valid(
+        &self,
+        params: S,
+    ) -> bool;
+
+    fn export(
+        &self,
+        params: S,
+    ) -> Result<()>;
+
+    /// Exports this item at its normal location (if it isn't already), then creates a relative
+    /// symlink to it at `dst`, so the same note/subject can appear under more than one location in
+    /// the shelf without duplicating its contents.
+    ///
+    /// The link target is computed with `helpers::fs::relative_path_from` against `dst`'s parent
+    /// directory, so the link stays valid if the shelf is later moved to a different location on
+    /// disk. If something already exists at `dst`, it is backed up out of the way first via
+    /// `helpers::fs::move_folder` rather than failing outright.
+    fn link(
+        &self,
+        params: S,
+        dst: &Path,
+    ) -> Result<()>
+    where
+        S: Copy,
+    {
+        self.export(params)?;
+
+        link_to(&self.path_in_shelf(params), dst)
+    }
+}
+
+/// Backs up whatever already exists at `dst` (if anything), then creates a relative symlink
+/// there pointing at `src`.
+fn link_to(
+    src: &Path,
+    dst: &Path,
+) -> Result<()> {
+    if dst.exists() {
+        let safety_string = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .to_string();
+        let backup_name = match dst.file_name() {
+            Some(name) => format!("{}-{}", name.to_string_lossy(), safety_string),
+            None => return Err(Error::ValueError),
+        };
+
+        helpers::fs::move_folder(dst, dst.with_file_name(backup_name), None)?;
+    }
+
+    let dst_parent = dst.parent().ok_or(Error::ValueError)?;
+    let target = helpers::fs::relative_path_from(src, dst_parent).ok_or(Error::ValueError)?;
+
+    helpers::fs::create_symlink(target, dst)
+}
+
+/// A trait implementing the object with the additional shelf-related data.
+pub trait ShelfData<S>: crate::Object + ShelfItem<S> {
+    fn data(
+        &self,
+        params: S,
+    ) -> toml::Value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile;
+
+    #[test]
+    fn shelf_from_valid_path() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+
+        assert!(Shelf::from(tmp_dir.path()).is_ok());
+    }
+
+    #[test]
+    fn shelf_from_invalid_path() {
+        let path = PathBuf::from("./this/path/does/not/exist");
+
+        assert!(Shelf::from(path).is_err());
+    }
+
+    #[test]
+    fn index_does_not_rescan_an_unchanged_directory() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let mut shelf = Shelf::from(tmp_dir.path()).unwrap();
+        shelf.export().unwrap();
+
+        let subject = Subject::new("Calculus");
+        subject.export(&shelf).unwrap();
+
+        let mut index = shelf.index();
+        let file_globs = vec!["*.tex".to_string()];
+
+        index
+            .notes_for_subject(&subject, &file_globs, &shelf)
+            .unwrap();
+        let first_revision = index.revision_of(&subject, &shelf);
+
+        index
+            .notes_for_subject(&subject, &file_globs, &shelf)
+            .unwrap();
+
+        assert_eq!(index.revision_of(&subject, &shelf), first_revision);
+    }
+
+    #[test]
+    fn resolve_folds_parent_dirs_against_the_shelf_path_without_requiring_existence() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let shelf = Shelf::from(tmp_dir.path()).unwrap();
+
+        let mut expected = shelf.path();
+        expected.push("common");
+
+        assert_eq!(shelf.resolve("subjects/../common"), expected);
+    }
+
+    #[test]
+    fn resolve_leaves_an_already_absolute_path_untouched() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let shelf = Shelf::from(tmp_dir.path()).unwrap();
+
+        assert_eq!(shelf.resolve(shelf.path()), shelf.path());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn link_item_creates_a_relative_symlink_resolving_back_to_the_subject() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let mut shelf = Shelf::from(tmp_dir.path()).unwrap();
+        shelf.export().unwrap();
+
+        let subject = Subject::new("Calculus");
+        let dst = shelf.resolve("Links/Calculus");
+        fs::create_dir_all(dst.parent().unwrap()).unwrap();
+
+        shelf.link_item(&subject, &dst).unwrap();
+
+        assert!(fs::symlink_metadata(&dst).unwrap().file_type().is_symlink());
+        assert_eq!(
+            fs::canonicalize(&dst).unwrap(),
+            fs::canonicalize(subject.path_in_shelf(&shelf)).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn link_item_backs_up_an_existing_destination_instead_of_failing() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let mut shelf = Shelf::from(tmp_dir.path()).unwrap();
+        shelf.export().unwrap();
+
+        let subject = Subject::new("Calculus");
+        let dst = shelf.resolve("Links/Calculus");
+        fs::create_dir_all(&dst).unwrap();
+
+        shelf.link_item(&subject, &dst).unwrap();
+
+        assert!(fs::symlink_metadata(&dst).unwrap().file_type().is_symlink());
+    }
+}
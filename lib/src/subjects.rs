@@ -50,7 +50,7 @@ impl ShelfData<&Shelf> for Subject {
         &self,
         shelf: &Shelf,
     ) -> toml::Value {
-        let mut subject_as_toml = match self.get_config(&shelf) {
+        let mut subject_as_toml = match self.resolved_config(&shelf) {
             Ok(v) => toml::Value::try_from(v).unwrap(),
             Err(_e) => toml::Value::from(HashMap::<String, toml::Value>::new()),
         };
@@ -77,7 +77,7 @@ impl ShelfItem<&Shelf> for Subject {
         let mut path = shelf.path();
         path.push(self.path());
 
-        path
+        helpers::fs::expand_path(path)
     }
 
     /// Checks if the associated path exists from the shelf.
@@ -144,12 +144,66 @@ impl Subject {
     ) -> Result<Self> {
         let subject = Subject::new(name);
         if !subject.is_item_valid(&shelf) {
-            return Err(Error::InvalidSubjectError(subject.path_in_shelf(&shelf)));
+            let suggestions = Subject::suggest(name, &shelf, 3)
+                .iter()
+                .map(|suggestion| suggestion.full_name().clone())
+                .collect();
+
+            return Err(Error::UnknownSubjectError(
+                subject.path_in_shelf(&shelf),
+                suggestions,
+            ));
         }
 
         Ok(subject)
     }
 
+    /// Scans the shelf for existing subjects and ranks them against `name` by edit distance,
+    /// returning up to `max` of the closest matches.
+    ///
+    /// Candidates are compared against both their `full_name()` and their kebab-cased `path()`,
+    /// case-insensitively, keeping only those within a distance of `name.len() / 3 + 1`.
+    pub fn suggest(
+        name: &str,
+        shelf: &Shelf,
+        max: usize,
+    ) -> Vec<Self> {
+        let threshold = name.len() / 3 + 1;
+        let name = name.to_lowercase();
+
+        let pattern = vec![format!("**/{}", SUBJECT_METADATA_FILE)];
+        let metadata_files = match globwalk::GlobWalkerBuilder::from_patterns(shelf.path(), &pattern)
+            .build()
+        {
+            Ok(walker) => walker,
+            Err(_e) => return vec![],
+        };
+
+        let mut ranked: Vec<(usize, Self)> = metadata_files
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.path().parent().map(|p| p.to_path_buf()))
+            .filter_map(|subject_dir| helpers::fs::relative_path_from(&subject_dir, &shelf.path()))
+            .map(|relative_path| Self::new(relative_path.to_string_lossy()))
+            .filter_map(|candidate| {
+                let full_name_distance =
+                    levenshtein_distance(&name, &candidate.full_name().to_lowercase());
+                let path_distance = levenshtein_distance(
+                    &name,
+                    &candidate.path().to_string_lossy().to_lowercase(),
+                );
+                let distance = full_name_distance.min(path_distance);
+
+                match distance <= threshold {
+                    true => Some((distance, candidate)),
+                    false => None,
+                }
+            })
+            .collect();
+
+        ranked.sort_by_key(|(distance, _candidate)| *distance);
+        ranked.into_iter().take(max).map(|(_distance, candidate)| candidate).collect()
+    }
+
     /// Searches for the subjects in the given shelf.
     pub fn from_vec<P: AsRef<str>>(
         subjects: &Vec<P>,
@@ -253,6 +307,30 @@ impl Subject {
         config::SubjectConfig::try_from(self.metadata_path_in_shelf(&shelf))
     }
 
+    /// Like `get_config`, but walks this subject's ancestors (via `split_subjects`) from the
+    /// root down to this subject, deep-merging each ancestor's `SubjectConfig` in order so that
+    /// settings defined on a parent subject (note templates, compile commands, metadata
+    /// defaults) apply to its children unless a child overrides them.
+    ///
+    /// Ancestors without a metadata file of their own are simply skipped.
+    pub fn resolved_config(
+        &self,
+        shelf: &Shelf,
+    ) -> Result<config::SubjectConfig> {
+        let mut resolved = config::SubjectConfig::new();
+
+        let mut ancestors = self.split_subjects();
+        ancestors.reverse();
+
+        for subject in ancestors {
+            if let Ok(config) = subject.get_config(&shelf) {
+                resolved.merge(&config);
+            }
+        }
+
+        Ok(resolved)
+    }
+
     /// Returns a vector of the parts of the subject.
     /// This does not check if each subject component is exported or valid.
     ///
@@ -309,12 +387,168 @@ impl Subject {
 
         Ok(notes)
     }
+
+    /// Returns this subject's immediate sub-subjects, i.e. nested directories in the shelf that
+    /// themselves have a `SUBJECT_METADATA_FILE`, without recursing any further down.
+    pub fn children(
+        &self,
+        shelf: &Shelf,
+    ) -> Vec<Self> {
+        self.descendants_with_options(&shelf, WalkOptions::new().max_depth(1))
+    }
+
+    /// Returns every descendant of this subject, recursively walking the shelf with the default
+    /// `WalkOptions` (unbounded depth, symlinks not followed, dot-directories skipped).
+    pub fn descendants(
+        &self,
+        shelf: &Shelf,
+    ) -> Vec<Self> {
+        self.descendants_with_options(&shelf, &WalkOptions::new())
+    }
+
+    /// Like `descendants`, but with caller-supplied `WalkOptions`, e.g. to cap the recursion
+    /// depth (as `children` does) or to follow symlinked directories.
+    ///
+    /// Only directories containing a `SUBJECT_METADATA_FILE` are yielded as subjects; other
+    /// directories are still walked into, since they may contain subjects further down.
+    pub fn descendants_with_options(
+        &self,
+        shelf: &Shelf,
+        options: &WalkOptions,
+    ) -> Vec<Self> {
+        let root = self.path_in_shelf(&shelf);
+        let pattern = vec![format!("**/{}", SUBJECT_METADATA_FILE)];
+
+        let walker = match globwalk::GlobWalkerBuilder::from_patterns(&root, &pattern)
+            .max_depth(options.max_depth.saturating_add(1))
+            .follow_links(options.follow_symlinks)
+            .build()
+        {
+            Ok(walker) => walker,
+            Err(_e) => return vec![],
+        };
+
+        walker
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.path().parent().map(|p| p.to_path_buf()))
+            .filter(|subject_dir| subject_dir != &root)
+            .filter(|subject_dir| {
+                options.include_hidden || !Self::has_hidden_component(subject_dir, &root)
+            })
+            .filter_map(|subject_dir| helpers::fs::relative_path_from(&subject_dir, &shelf.path()))
+            .map(|relative_path| Self::new(relative_path.to_string_lossy()))
+            .collect()
+    }
+
+    /// Checks whether any path component of `path`, relative to `root`, starts with a dot.
+    fn has_hidden_component(
+        path: &PathBuf,
+        root: &PathBuf,
+    ) -> bool {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .any(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .map(|name| name.starts_with('.'))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// Options controlling a recursive subject-tree walk; see `Subject::descendants_with_options`.
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    max_depth: usize,
+    follow_symlinks: bool,
+    include_hidden: bool,
+}
+
+impl WalkOptions {
+    /// Creates a new instance of the walk options.
+    /// By default, the walk is unbounded in depth, does not follow symlinks, and skips
+    /// dot-directories.
+    pub fn new() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            follow_symlinks: false,
+            include_hidden: false,
+        }
+    }
+
+    /// Sets the maximum number of directory levels to descend, relative to the subject being
+    /// walked.
+    pub fn max_depth(
+        &mut self,
+        max_depth: usize,
+    ) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets whether the walk follows symlinked directories.
+    pub fn follow_symlinks(
+        &mut self,
+        follow_symlinks: bool,
+    ) -> &mut Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Sets whether dot-directories (e.g. `.git`) are walked into and yielded.
+    pub fn include_hidden(
+        &mut self,
+        include_hidden: bool,
+    ) -> &mut Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+}
+
+/// Computes the Levenshtein (edit) distance between two strings.
+fn levenshtein_distance(
+    a: &str,
+    b: &str,
+) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for i in 0..=a.len() {
+        d[i][0] = i;
+    }
+    for j in 0..=b.len() {
+        d[0][j] = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+        }
+    }
+
+    d[a.len()][b.len()]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn levenshtein_distance_basic() {
+        assert_eq!(levenshtein_distance("calculus", "calculus"), 0);
+        assert_eq!(levenshtein_distance("calculus", "calculas"), 1);
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+    }
+
     #[test]
     fn basic_subject() {
         let subject = Subject::new("Calculus");
@@ -443,6 +677,83 @@ mod tests {
         assert!(subject_part.next().is_none());
     }
 
+    #[test]
+    fn descendants_are_discovered_recursively() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let mut shelf = Shelf::from(tmp_dir.path()).unwrap();
+        shelf.export().unwrap();
+
+        let root = Subject::new("Mathematics");
+        let calculus = Subject::new("Mathematics/Calculus");
+        let linear_algebra = Subject::new("Mathematics/Linear Algebra");
+        let multivariable = Subject::new("Mathematics/Calculus/Multivariable");
+
+        root.export(&shelf).unwrap();
+        calculus.export(&shelf).unwrap();
+        linear_algebra.export(&shelf).unwrap();
+        multivariable.export(&shelf).unwrap();
+
+        for subject in [&root, &calculus, &linear_algebra, &multivariable] {
+            std::fs::write(subject.metadata_path_in_shelf(&shelf), "").unwrap();
+        }
+
+        let descendants = root.descendants(&shelf);
+        assert_eq!(descendants.len(), 3);
+        assert!(descendants
+            .iter()
+            .any(|subject| subject.path() == calculus.path()));
+        assert!(descendants
+            .iter()
+            .any(|subject| subject.path() == multivariable.path()));
+
+        let children = root.children(&shelf);
+        assert_eq!(children.len(), 2);
+        assert!(!children
+            .iter()
+            .any(|subject| subject.path() == multivariable.path()));
+    }
+
+    #[test]
+    fn resolved_config_merges_ancestors_root_to_leaf() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let mut shelf = Shelf::from(tmp_dir.path()).unwrap();
+        shelf.export().unwrap();
+
+        let parent = Subject::new("Bachelor I");
+        let child = Subject::new("Bachelor I/Calculus");
+
+        parent.export(&shelf).unwrap();
+        child.export(&shelf).unwrap();
+
+        std::fs::write(
+            parent.metadata_path_in_shelf(&shelf),
+            "command = \"latexmk\"\n[template]\ndefault = \"article.hbs\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            child.metadata_path_in_shelf(&shelf),
+            "[template]\ndefault = \"notes.hbs\"\n",
+        )
+        .unwrap();
+
+        let resolved = child.resolved_config(&shelf).unwrap();
+
+        assert_eq!(
+            resolved.as_value().get("command").unwrap().as_str(),
+            Some("latexmk")
+        );
+        assert_eq!(
+            resolved
+                .as_value()
+                .get("template")
+                .unwrap()
+                .get("default")
+                .unwrap()
+                .as_str(),
+            Some("notes.hbs")
+        );
+    }
+
     #[test]
     fn basic_note() {
         let subject = Subject::new("Calculus");
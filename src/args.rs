@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+/// Texture Notes is a note organizer and compiler with LaTeX in mind.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "lanoma")]
+pub struct Lanoma {
+    /// Sets the shelf to operate from.
+    /// By default, it uses the current working directory.
+    #[structopt(short, long, parse(from_os_str))]
+    pub shelf: Option<PathBuf>,
+
+    /// Sets the profile to use.
+    /// By default, it uses the app's own config directory.
+    #[structopt(short, long, parse(from_os_str))]
+    pub profile: Option<PathBuf>,
+
+    /// Prompts for any required subject or note titles that were not given on the command line,
+    /// instead of failing outright.
+    #[structopt(short, long)]
+    pub interactive: bool,
+
+    #[structopt(subcommand)]
+    pub cmd: Command,
+}
+
+/// The set of subjects and notes to operate on.
+#[derive(StructOpt, Debug)]
+pub enum Input {
+    /// Operate on notes under a given subject.
+    Notes {
+        /// The subject the notes belong to.
+        /// Can be omitted when `--interactive` is set, in which case it will be prompted for.
+        subject: Option<String>,
+
+        /// The titles of the notes.
+        /// Can be left empty when `--interactive` is set, in which case they will be prompted for.
+        notes: Vec<String>,
+    },
+
+    /// Operate on one or more subjects.
+    Subjects {
+        /// The subject names.
+        subjects: Vec<String>,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Initializes a new profile.
+    Init {
+        /// The name of the profile.
+        name: Option<String>,
+    },
+
+    /// Adds new notes or subjects in the shelf.
+    Add {
+        #[structopt(subcommand)]
+        kind: Input,
+
+        /// Allows overwriting already existing notes or subjects.
+        #[structopt(long = "not-strict")]
+        not_strict: bool,
+
+        /// The template to use for the notes being created.
+        #[structopt(short, long)]
+        template: Option<String>,
+
+        /// Opens the created/target notes in `$EDITOR` (or `$VISUAL`) once they are written.
+        #[structopt(long)]
+        open: bool,
+    },
+
+    /// Removes notes or subjects from the shelf.
+    Remove {
+        #[structopt(subcommand)]
+        kind: Input,
+    },
+
+    /// Opens notes in `$EDITOR` (or `$VISUAL`) without modifying them.
+    Edit {
+        /// The subject the notes belong to.
+        subject: String,
+
+        /// The titles of the notes to open.
+        notes: Vec<String>,
+    },
+
+    /// Compiles the notes of the given subjects.
+    Compile {
+        #[structopt(subcommand)]
+        kind: Input,
+
+        /// The number of threads to use for compiling.
+        #[structopt(short = "j", long, default_value = "4")]
+        thread_count: usize,
+
+        /// The file glob patterns to use for collecting the notes to compile.
+        #[structopt(short, long)]
+        files: Option<Vec<String>>,
+
+        /// The command template to use instead of the subject's configured command.
+        #[structopt(short, long)]
+        command: Option<String>,
+
+        /// Instead of compiling, write a `compile_commands.json`-style manifest describing the
+        /// resolved working directory, source path, and argv for each compilable note.
+        /// Pass `-` to write the manifest to stdout.
+        #[structopt(long, parse(from_os_str))]
+        emit_commands: Option<PathBuf>,
+
+        /// Recompiles every note regardless of the shelf's build manifest.
+        #[structopt(long = "force", alias = "no-incremental")]
+        force: bool,
+    },
+
+    /// Compiles a master note consolidating several subjects into one document.
+    Master {
+        /// The subjects to be consolidated into the master note.
+        subjects: Vec<String>,
+
+        /// Skips the compilation step, only generating the master note file.
+        #[structopt(long)]
+        skip_compilation: bool,
+
+        /// The file glob patterns to use for collecting the notes to consolidate.
+        #[structopt(short, long)]
+        files: Option<Vec<String>>,
+
+        /// The template to use for the master note.
+        #[structopt(short, long)]
+        template: Option<String>,
+
+        /// The command template to use instead of the subject's configured command.
+        #[structopt(short, long)]
+        command: Option<String>,
+
+        /// Recompiles every master note regardless of the shelf's build manifest.
+        #[structopt(long = "force", alias = "no-incremental")]
+        force: bool,
+    },
+}
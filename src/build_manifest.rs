@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lanoma_lib::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use sha2::{Digest, Sha256};
+
+/// The name of the persistent build manifest kept at the shelf root.
+pub const BUILD_MANIFEST_FILENAME: &str = ".lanoma-build.json";
+
+/// A single note's last recorded compilation state.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BuildEntry {
+    pub source_hash: String,
+    pub command_hash: String,
+    pub succeeded: bool,
+}
+
+/// A persistent, per-shelf manifest recording the last known build state of every note,
+/// so that unchanged notes can skip recompilation.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BuildManifest {
+    entries: HashMap<String, BuildEntry>,
+}
+
+impl BuildManifest {
+    /// Returns the manifest's path for the given shelf root.
+    pub fn path_in_shelf<P: AsRef<Path>>(shelf_path: P) -> PathBuf {
+        let mut path = shelf_path.as_ref().to_path_buf();
+        path.push(BUILD_MANIFEST_FILENAME);
+
+        path
+    }
+
+    /// Loads the manifest from the given shelf root.
+    ///
+    /// If the file is missing or cannot be parsed (e.g. from an older format), an empty
+    /// manifest is returned instead of failing, since the manifest is purely a cache.
+    pub fn load<P: AsRef<Path>>(shelf_path: P) -> Self {
+        fs::read_to_string(Self::path_in_shelf(shelf_path))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persists the manifest to the given shelf root.
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        shelf_path: P,
+    ) -> Result<(), Error> {
+        let contents = serde_json::to_string_pretty(self).map_err(Error::SerdeValueError)?;
+
+        fs::write(Self::path_in_shelf(shelf_path), contents).map_err(Error::IoError)
+    }
+
+    /// Whether the entry for `key` is still valid: the source and command hashes are unchanged,
+    /// the prior run succeeded, and the expected output artifact still exists on disk.
+    pub fn is_up_to_date(
+        &self,
+        key: &str,
+        source_hash: &str,
+        command_hash: &str,
+        output_exists: bool,
+    ) -> bool {
+        match self.entries.get(key) {
+            Some(entry) => {
+                entry.succeeded
+                    && output_exists
+                    && entry.source_hash == source_hash
+                    && entry.command_hash == command_hash
+            }
+            None => false,
+        }
+    }
+
+    /// Records the outcome of compiling (or skipping) the note at `key`.
+    pub fn record<S: Into<String>>(
+        &mut self,
+        key: S,
+        source_hash: String,
+        command_hash: String,
+        succeeded: bool,
+    ) {
+        self.entries.insert(
+            key.into(),
+            BuildEntry {
+                source_hash,
+                command_hash,
+                succeeded,
+            },
+        );
+    }
+}
+
+/// Hashes the given bytes with SHA-256, returning the lowercase hex digest.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hashes a note's file contents and its command template separately, returning
+/// `(source_hash, command_hash)`, so that a change to either invalidates the note's build entry.
+pub fn hash_note_build<S: AsRef<str>>(
+    source_contents: &[u8],
+    command_template: S,
+) -> (String, String) {
+    let source_hash = hash_bytes(source_contents);
+    let command_hash = hash_bytes(command_template.as_ref().as_bytes());
+
+    (source_hash, command_hash)
+}
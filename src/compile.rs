@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+use std::process;
+
+use lanoma_lib::error::Error;
+use rayon::prelude::*;
+
+/// Something that can be turned into a shell command to be compiled (e.g., a note or a master note).
+pub trait Compilable: Send + Sync {
+    /// The title used for reporting compilation results.
+    fn title(&self) -> String;
+
+    /// The absolute path of the source file this item compiles from.
+    fn source_path(&self) -> PathBuf;
+
+    /// The expected output artifact produced by a successful compilation.
+    ///
+    /// Defaults to the source path with its extension swapped for `pdf`, which holds for the
+    /// default `latexmk -pdf` command; override this for compilables with a different output.
+    fn output_path(&self) -> PathBuf {
+        self.source_path().with_extension("pdf")
+    }
+
+    /// Builds the command to be run for compiling this item, using the given command template.
+    fn to_command(
+        &self,
+        command_template: &str,
+    ) -> process::Command;
+}
+
+/// A single entry of a `compile_commands.json`-style manifest, describing how one compilable
+/// note would be built without actually running the compiler.
+#[derive(serde::Serialize)]
+pub struct CommandManifestEntry {
+    pub directory: PathBuf,
+    pub file: PathBuf,
+    pub arguments: Vec<String>,
+}
+
+/// A compilable item's title alongside the key (its source path) it was recorded under in the
+/// build manifest, so callers can look up its build hashes without the title alone, which is
+/// not necessarily unique across subjects.
+pub struct CompiledNote {
+    pub key: String,
+    pub title: String,
+}
+
+/// The result of compiling a single `CompilationEnvironment`.
+pub struct CompileResult {
+    pub path: PathBuf,
+    pub compiled: Vec<CompiledNote>,
+    pub failed: Vec<CompiledNote>,
+}
+
+/// An environment describing a batch of compilable items sharing a working directory and command.
+pub struct CompilationEnvironment {
+    path: PathBuf,
+    command: String,
+    thread_count: i16,
+    pub compilables: Vec<Box<dyn Compilable>>,
+}
+
+impl CompilationEnvironment {
+    /// Creates a new compilation environment rooted at the given path.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            command: String::new(),
+            thread_count: 1,
+            compilables: vec![],
+        }
+    }
+
+    pub fn compilables(
+        &mut self,
+        compilables: Vec<Box<dyn Compilable>>,
+    ) -> &mut Self {
+        self.compilables = compilables;
+        self
+    }
+
+    pub fn command<S: AsRef<str>>(
+        &mut self,
+        command: S,
+    ) -> &mut Self {
+        self.command = command.as_ref().to_string();
+        self
+    }
+
+    pub fn thread_count(
+        &mut self,
+        thread_count: i16,
+    ) -> &mut Self {
+        self.thread_count = thread_count;
+        self
+    }
+
+    /// Resolves the command for every compilable in this environment without running it,
+    /// producing a `compile_commands.json`-style manifest entry for each.
+    pub fn to_manifest(&self) -> Vec<CommandManifestEntry> {
+        self.compilables
+            .iter()
+            .map(|compilable| {
+                let cmd = compilable.to_command(&self.command);
+
+                let mut arguments = vec![cmd.get_program().to_string_lossy().to_string()];
+                arguments.extend(cmd.get_args().map(|arg| arg.to_string_lossy().to_string()));
+
+                CommandManifestEntry {
+                    directory: self.path.clone(),
+                    file: compilable.source_path(),
+                    arguments,
+                }
+            })
+            .collect()
+    }
+
+    /// Compiles every item in this environment in parallel, reporting which ones succeeded and failed.
+    pub fn compile(self) -> Result<CompileResult, Error> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.thread_count.max(1) as usize)
+            .build()
+            .map_err(|_e| Error::ValueError)?;
+
+        let command = &self.command;
+        let path = &self.path;
+
+        let (compiled, failed): (Vec<CompiledNote>, Vec<CompiledNote>) = pool.install(|| {
+            self.compilables
+                .par_iter()
+                .map(|compilable| {
+                    let mut cmd = compilable.to_command(command);
+                    cmd.current_dir(path);
+
+                    let success = cmd.output().map(|o| o.status.success()).unwrap_or(false);
+
+                    let note = CompiledNote {
+                        key: compilable.source_path().to_string_lossy().to_string(),
+                        title: compilable.title(),
+                    };
+
+                    (note, success)
+                })
+                .partition_map(|(note, success)| match success {
+                    true => rayon::iter::Either::Left(note),
+                    false => rayon::iter::Either::Right(note),
+                })
+        });
+
+        Ok(CompileResult {
+            path: self.path,
+            compiled,
+            failed,
+        })
+    }
+}
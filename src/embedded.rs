@@ -0,0 +1,10 @@
+use rust_embed::RustEmbed;
+
+/// The built-in template set baked into the binary at compile time.
+///
+/// `TemplateHandlebarsRegistry::new()` seeds itself from these assets so a profile is renderable
+/// immediately, even with an empty or absent `.templates` directory; any on-disk template
+/// registered afterward under the same name transparently takes its place.
+#[derive(RustEmbed)]
+#[folder = "src/embedded_templates/"]
+pub struct EmbeddedTemplates;
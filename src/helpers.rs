@@ -0,0 +1,204 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use dialoguer::{Input as DialoguerInput, Select};
+use globwalk;
+use lanoma_lib::error::Error;
+use lanoma_lib::masternote::MasterNote;
+use lanoma_lib::note::Note;
+use lanoma_lib::profile::Profile;
+use lanoma_lib::shelf::{Shelf, ShelfItem};
+use lanoma_lib::subjects::Subject;
+use lanoma_lib::Object;
+use toml::{self, Value};
+
+// re-exported for convenience so the rest of the binary only has to reach into one module
+pub use lanoma_lib::helpers::fs::relative_path_from;
+
+/// Builds the render context for a single note, combining the profile, shelf, subject, and note data.
+pub fn note_full_object(
+    profile: &Profile,
+    shelf: &Shelf,
+    note: &Note,
+    subject: &Subject,
+) -> Value {
+    let mut object = profile.data();
+
+    if let Value::Table(ref mut table) = object {
+        table.insert("note".to_string(), note.data());
+        table.insert("subject".to_string(), subject.data(&shelf));
+    }
+
+    object
+}
+
+/// Builds the render context for a master note, combining the profile, shelf, and master note data.
+pub fn master_note_full_object(
+    profile: &Profile,
+    shelf: &Shelf,
+    master_note: &MasterNote,
+) -> Value {
+    let mut object = profile.data();
+
+    if let Value::Table(ref mut table) = object {
+        table.insert("master_note".to_string(), master_note.data(&shelf));
+    }
+
+    object
+}
+
+/// Creates a `MasterNote` out of a subject string, collecting its notes from the filesystem.
+pub fn create_master_note_from_subject_str(
+    subject_str: &str,
+    shelf: &Shelf,
+    files: &Option<Vec<String>>,
+) -> Result<MasterNote, Error> {
+    let subject = Subject::from_shelf(subject_str, &shelf)?;
+    let subject_config = subject.get_config(&shelf).unwrap_or_default();
+    let file_filter = files.clone().unwrap_or_else(|| subject_config.files());
+
+    let notes = subject.get_notes_in_fs(&file_filter, &shelf)?;
+
+    Ok(MasterNote::new(subject, notes))
+}
+
+/// Writes the given contents to the given path.
+///
+/// If `strict` is set, it will not overwrite an already existing file.
+pub fn write_file<P: AsRef<Path>, S: AsRef<str>>(
+    path: P,
+    contents: S,
+    strict: bool,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(Error::IoError)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(!strict)
+        .create_new(strict)
+        .open(path)
+        .map_err(Error::IoError)?;
+
+    file.write_all(contents.as_ref().as_bytes())
+        .map_err(Error::IoError)?;
+
+    Ok(())
+}
+
+/// Lists the subject folders (those containing a metadata file) found anywhere under the shelf,
+/// nested subjects included.
+///
+/// The names returned are the on-disk (kebab-case) subject paths relative to the shelf, not the
+/// subjects' original display names.
+pub fn list_subject_names(shelf: &Shelf) -> Vec<String> {
+    // Mirrors `lanoma_lib::subjects::SUBJECT_METADATA_FILE`, which is private to the lib crate.
+    let pattern = vec![String::from("**/info.toml")];
+
+    let metadata_files =
+        match globwalk::GlobWalkerBuilder::from_patterns(shelf.path(), &pattern).build() {
+            Ok(walker) => walker,
+            Err(_e) => return vec![],
+        };
+
+    metadata_files
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.path().parent().map(|p| p.to_path_buf()))
+        .filter_map(|subject_dir| {
+            relative_path_from(&subject_dir, &shelf.path())
+                .map(|p| p.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+/// Resolves a note command's subject and note titles, prompting the user for whichever is
+/// missing when `interactive` is set.
+///
+/// Existing subjects in the shelf are offered as selectable choices; note titles are read as
+/// free text, one per line, terminated by an empty line.
+pub fn resolve_notes_input(
+    subject: Option<String>,
+    notes: Vec<String>,
+    shelf: &Shelf,
+    interactive: bool,
+) -> Result<(String, Vec<String>), Error> {
+    let subject = match subject {
+        Some(subject) => subject,
+        None if interactive => {
+            let choices = list_subject_names(shelf);
+
+            if choices.is_empty() {
+                DialoguerInput::new()
+                    .with_prompt("Subject")
+                    .interact_text()
+                    .map_err(|_e| Error::ValueError)?
+            } else {
+                let selection = Select::new()
+                    .with_prompt("Subject")
+                    .items(&choices)
+                    .interact()
+                    .map_err(|_e| Error::ValueError)?;
+
+                choices[selection].clone()
+            }
+        }
+        None => return Err(Error::MissingDataError("subject".to_string())),
+    };
+
+    let notes = if notes.is_empty() && interactive {
+        let mut collected = vec![];
+
+        loop {
+            let title: String = DialoguerInput::new()
+                .with_prompt("Note title (leave empty to finish)")
+                .allow_empty(true)
+                .interact_text()
+                .map_err(|_e| Error::ValueError)?;
+
+            if title.is_empty() {
+                break;
+            }
+
+            collected.push(title);
+        }
+
+        collected
+    } else if notes.is_empty() {
+        return Err(Error::MissingDataError("notes".to_string()));
+    } else {
+        notes
+    };
+
+    Ok((subject, notes))
+}
+
+/// Resolves the user's preferred editor, falling back from `$EDITOR` to `$VISUAL`.
+pub fn resolve_editor() -> Option<String> {
+    std::env::var("EDITOR")
+        .ok()
+        .or_else(|| std::env::var("VISUAL").ok())
+}
+
+/// Opens the given paths in the user's resolved editor, waiting for it to exit.
+pub fn open_in_editor(paths: &[PathBuf]) -> Result<(), Error> {
+    let editor = match resolve_editor() {
+        Some(editor) => editor,
+        None => return Err(Error::MissingDataError("$EDITOR or $VISUAL".to_string())),
+    };
+
+    let status = std::process::Command::new(editor)
+        .args(paths)
+        .status()
+        .map_err(Error::IoError)?;
+
+    if !status.success() {
+        return Err(Error::ProcessError(status));
+    }
+
+    Ok(())
+}
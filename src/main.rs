@@ -1,9 +1,9 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::process;
 
 use directories;
-use lanoma_lib::config::SubjectConfig;
 use lanoma_lib::error::Error;
 use lanoma_lib::masternote::MasterNote;
 use lanoma_lib::note::Note;
@@ -13,18 +13,75 @@ use lanoma_lib::profile::{
 use lanoma_lib::shelf::{ExportOptions, Shelf, ShelfItem};
 use lanoma_lib::subjects::Subject;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde_json;
 use structopt::StructOpt;
 
 // the modules from this crate
 mod args;
+mod build_manifest;
 mod compile;
 mod helpers;
 
 use crate::args::{Command, Input, Lanoma};
+use crate::build_manifest::BuildManifest;
 use crate::compile::{Compilable, CompilationEnvironment};
 
 static EXIT_STATUS: i32 = 1;
 
+/// A note's hashes as recorded right before it was (or wasn't) queued for compilation, so the
+/// build manifest can be updated once the actual compile results are known.
+struct NoteBuildHashes {
+    key: String,
+    source_hash: String,
+    command_hash: String,
+}
+
+/// Filters notes down to the ones that actually need (re)compiling, consulting the build
+/// manifest for entries whose source and command hashes are unchanged, whose last run
+/// succeeded, and whose output artifact still exists on disk. Skipped notes are left out of
+/// the returned compilables entirely.
+///
+/// Returns the compilables to run alongside the hashes computed for each of them, keyed by the
+/// same source-path key used by the build manifest, so the caller can update the manifest once
+/// compilation finishes.
+fn filter_notes_incrementally(
+    notes: Vec<Note>,
+    subject: &Subject,
+    shelf: &Shelf,
+    command_template: &str,
+    manifest: &BuildManifest,
+    force: bool,
+) -> (Vec<Box<dyn Compilable>>, HashMap<String, NoteBuildHashes>) {
+    let mut compilables: Vec<Box<dyn Compilable>> = vec![];
+    let mut hashes = HashMap::new();
+
+    for note in notes {
+        let source_path = note.path_in_shelf((subject, shelf));
+        let key = source_path.to_string_lossy().to_string();
+        let source_contents = fs::read(&source_path).unwrap_or_default();
+        let (source_hash, command_hash) =
+            build_manifest::hash_note_build(&source_contents, command_template);
+
+        let output_exists = source_path.with_extension("pdf").exists();
+        let up_to_date = !force
+            && manifest.is_up_to_date(&key, &source_hash, &command_hash, output_exists);
+
+        if !up_to_date {
+            hashes.insert(
+                key.clone(),
+                NoteBuildHashes {
+                    key,
+                    source_hash,
+                    command_hash,
+                },
+            );
+            compilables.push(Box::new(note));
+        }
+    }
+
+    (compilables, hashes)
+}
+
 fn main() {
     let args = Lanoma::from_args();
 
@@ -53,6 +110,8 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
         None => config_app_dir,
     };
 
+    let interactive = args.interactive;
+
     match args.cmd {
         Command::Init { name } => {
             let mut profile_builder = ProfileBuilder::new();
@@ -74,6 +133,7 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
             kind,
             not_strict,
             template,
+            open,
         } => {
             let profile = Profile::from(&profile_path)?;
             let mut export_options = ExportOptions::new();
@@ -81,6 +141,8 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
 
             match kind {
                 Input::Notes { subject, notes } => {
+                    let (subject, notes) =
+                        helpers::resolve_notes_input(subject, notes, &shelf, interactive)?;
                     let subject = Subject::from_shelf(&subject, &shelf)?;
                     let notes: Vec<Note> = notes.iter().map(|note| Note::new(note)).collect();
 
@@ -115,10 +177,19 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
                         );
                     } else {
                         println!("Here are the notes under the subject {:?} that successfully created in the shelf.", subject.name());
-                        for note in created_notes {
+                        for note in created_notes.iter() {
                             println!("  - {:?}", note.title());
                         }
                     }
+
+                    if open {
+                        let note_paths: Vec<_> = created_notes
+                            .iter()
+                            .map(|note| note.path_in_shelf((&subject, &shelf)))
+                            .collect();
+
+                        helpers::open_in_editor(&note_paths)?;
+                    }
                 }
                 Input::Subjects { subjects } => {
                     let created_subjects: Vec<Subject> = Subject::from_vec_loose(&subjects, &shelf)
@@ -155,6 +226,8 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
                 }
             }
             Input::Notes { subject, notes } => {
+                let (subject, notes) =
+                    helpers::resolve_notes_input(subject, notes, &shelf, interactive)?;
                 let subject = Subject::from_shelf(&subject, &shelf)?;
                 let deleted_notes: Vec<Note> = Note::from_vec_loose(&notes, &subject, &shelf)
                     .into_iter()
@@ -176,25 +249,44 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
             thread_count,
             files,
             command,
+            emit_commands,
+            force,
         } => {
             let _profile = Profile::from(&profile_path)?;
             let shelf_path = shelf.path();
 
+            let mut build_manifest = BuildManifest::load(&shelf_path);
+            let mut note_hashes: HashMap<String, NoteBuildHashes> = HashMap::new();
+
+            // `--emit-commands` is a dry-run view of how every compilable note *would* build, not
+            // just the ones the incremental cache considers stale, so bypass the cache entirely
+            // for it rather than silently omitting up-to-date notes from the manifest.
+            let skip_incremental_cache = force || emit_commands.is_some();
+
             let compiled_notes_envs = match kind {
                 Input::Notes { subject, notes } => {
+                    let (subject, notes) =
+                        helpers::resolve_notes_input(subject, notes, &shelf, interactive)?;
                     let subject = Subject::from_shelf(&subject, &shelf)?;
-                    let subject_config = subject.get_config(&shelf).unwrap_or(SubjectConfig::new());
+                    let subject_config = subject.get_config(&shelf).unwrap_or_default();
+                    let command_template =
+                        command.clone().unwrap_or_else(|| subject_config.command());
                     let notes = Note::from_vec_loose(&notes, &subject, &shelf);
-                    let mut compilables: Vec<Box<dyn Compilable>> = vec![];
-                    for note in notes {
-                        compilables.push(Box::new(note));
-                    }
+                    let (compilables, hashes) = filter_notes_incrementally(
+                        notes,
+                        &subject,
+                        &shelf,
+                        &command_template,
+                        &build_manifest,
+                        skip_incremental_cache,
+                    );
+                    note_hashes.extend(hashes);
 
                     let mut compiled_notes_env =
                         CompilationEnvironment::new(subject.path_in_shelf(&shelf));
                     compiled_notes_env
                         .compilables(compilables)
-                        .command(command.as_ref().unwrap_or(&subject_config.command))
+                        .command(&command_template)
                         .thread_count(thread_count as i16);
                     vec![compiled_notes_env]
                 }
@@ -203,18 +295,24 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
 
                     for subject in subjects.iter() {
                         let subject = Subject::from_shelf(&subject, &shelf)?;
-                        let subject_config =
-                            subject.get_config(&shelf).unwrap_or(SubjectConfig::new());
-                        let file_filter = files.as_ref().unwrap_or(&subject_config.files);
+                        let subject_config = subject.get_config(&shelf).unwrap_or_default();
+                        let file_filter = files.clone().unwrap_or_else(|| subject_config.files());
+                        let command_template =
+                            command.clone().unwrap_or_else(|| subject_config.command());
 
                         let notes = subject.get_notes_in_fs(&file_filter, &shelf)?;
-                        let mut compilables: Vec<Box<dyn Compilable>> = vec![];
-                        for note in notes {
-                            compilables.push(Box::new(note));
-                        }
+                        let (compilables, hashes) = filter_notes_incrementally(
+                            notes,
+                            &subject,
+                            &shelf,
+                            &command_template,
+                            &build_manifest,
+                            skip_incremental_cache,
+                        );
+                        note_hashes.extend(hashes);
 
                         let mut env = CompilationEnvironment::new(subject.path_in_shelf(&shelf));
-                        env.command(command.as_ref().unwrap_or(&subject_config.command))
+                        env.command(&command_template)
                             .compilables(compilables)
                             .thread_count(thread_count as i16);
 
@@ -225,6 +323,25 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
                 }
             };
 
+            if let Some(emit_commands) = emit_commands {
+                let manifest: Vec<_> = compiled_notes_envs
+                    .iter()
+                    .filter(|comp_env| !comp_env.compilables.is_empty())
+                    .flat_map(|comp_env| comp_env.to_manifest())
+                    .collect();
+
+                let manifest_string =
+                    serde_json::to_string_pretty(&manifest).map_err(Error::SerdeValueError)?;
+
+                if emit_commands == std::path::Path::new("-") {
+                    println!("{}", manifest_string);
+                } else {
+                    fs::write(&emit_commands, manifest_string).map_err(Error::IoError)?;
+                }
+
+                return Ok(());
+            }
+
             compiled_notes_envs
                 .into_iter()
                 .filter(|comp_env| !comp_env.compilables.is_empty())
@@ -234,23 +351,54 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
                     println!(
                         "\n\n----\nAt {:?}:\n----\n",
                         helpers::relative_path_from(&compile_result.path, &shelf_path)
-                            .unwrap_or(compile_result.path)
+                            .unwrap_or(compile_result.path.clone())
                     );
 
                     if !compile_result.compiled.is_empty() {
                         println!("Notes that succeeded to compile:");
-                        for compiled in compile_result.compiled {
-                            println!("  - {}", compiled);
+                        for compiled in compile_result.compiled.iter() {
+                            println!("  - {}", compiled.title);
+
+                            if let Some(hashes) = note_hashes.get(&compiled.key) {
+                                build_manifest.record(
+                                    hashes.key.clone(),
+                                    hashes.source_hash.clone(),
+                                    hashes.command_hash.clone(),
+                                    true,
+                                );
+                            }
                         }
                     }
 
                     if !compile_result.failed.is_empty() {
                         println!("Notes that failed to compile:");
-                        for failed in compile_result.failed {
-                            println!("  - {}", failed);
+                        for failed in compile_result.failed.iter() {
+                            println!("  - {}", failed.title);
+
+                            if let Some(hashes) = note_hashes.get(&failed.key) {
+                                build_manifest.record(
+                                    hashes.key.clone(),
+                                    hashes.source_hash.clone(),
+                                    hashes.command_hash.clone(),
+                                    false,
+                                );
+                            }
                         }
                     }
-                })
+                });
+
+            build_manifest.save(&shelf_path)?;
+        }
+        Command::Edit { subject, notes } => {
+            let subject = Subject::from_shelf(&subject, &shelf)?;
+            let notes = Note::from_vec_loose(&notes, &subject, &shelf);
+
+            let note_paths: Vec<_> = notes
+                .iter()
+                .map(|note| note.path_in_shelf((&subject, &shelf)))
+                .collect();
+
+            helpers::open_in_editor(&note_paths)?;
         }
         Command::Master {
             subjects,
@@ -258,8 +406,11 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
             files,
             template,
             command,
+            force,
         } => {
             let profile = Profile::from(&profile_path)?;
+            let shelf_path = shelf.path();
+            let build_manifest = std::sync::Mutex::new(BuildManifest::load(&shelf_path));
 
             let compiled_master_notes: Vec<MasterNote> = subjects
                 .into_par_iter()
@@ -290,24 +441,47 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
                 })
                 .filter(|master_note| {
                     if !skip_compilation {
+                        let config = master_note.subject().get_config(&shelf).unwrap_or_default();
+                        let command_template = command.clone().unwrap_or_else(|| config.command());
+
+                        let master_note_path = master_note.path_in_shelf(&shelf);
+                        let key = master_note_path.to_string_lossy().to_string();
+                        let source_contents = fs::read(&master_note_path).unwrap_or_default();
+                        let (source_hash, command_hash) =
+                            build_manifest::hash_note_build(&source_contents, &command_template);
+                        let output_exists = master_note_path.with_extension("pdf").exists();
+
+                        if !force
+                            && build_manifest.lock().unwrap().is_up_to_date(
+                                &key,
+                                &source_hash,
+                                &command_hash,
+                                output_exists,
+                            )
+                        {
+                            return false;
+                        }
+
                         let original_dir = env::current_dir().map_err(Error::IoError).unwrap();
                         let compilation_dst = master_note.subject().path_in_shelf(&shelf);
-                        let config = master_note
-                            .subject()
-                            .get_config(&shelf)
-                            .unwrap_or(SubjectConfig::new());
 
                         env::set_current_dir(&compilation_dst)
                             .map_err(Error::IoError)
                             .unwrap();
                         let mut master_note_compilation_cmd =
-                            master_note.to_command(command.as_ref().unwrap_or(&config.command));
+                            master_note.to_command(&command_template);
                         let output = master_note_compilation_cmd.output().unwrap();
                         env::set_current_dir(original_dir)
                             .map_err(Error::IoError)
                             .unwrap();
 
-                        output.status.success()
+                        let succeeded = output.status.success();
+                        build_manifest
+                            .lock()
+                            .unwrap()
+                            .record(key, source_hash, command_hash, succeeded);
+
+                        succeeded
                     } else {
                         false
                     }
@@ -324,6 +498,8 @@ fn parse_from_args(args: Lanoma) -> Result<(), Error> {
                     println!("  - {:?}", note.title());
                 }
             }
+
+            build_manifest.into_inner().unwrap().save(&shelf_path)?;
         }
         _ => (),
     }
@@ -7,7 +7,6 @@ use std::path::{Path, PathBuf};
 use toml::{self, Value};
 
 use crate::config::ProfileConfig;
-use crate::consts;
 use crate::error::Error;
 use crate::helpers;
 use crate::templates::{self, TemplateGetter};
@@ -16,6 +15,7 @@ use crate::{Object, Result};
 // profile constants
 pub const PROFILE_METADATA_FILENAME: &str = ".profile.toml";
 pub const PROFILE_TEMPLATE_FILES_DIR_NAME: &str = ".templates";
+pub const PROFILE_TEMPLATE_HELPERS_DIR_NAME: &str = "helpers";
 
 pub const TEMPLATE_FILE_EXTENSION: &str = "hbs";
 pub const PROFILE_NOTE_TEMPLATE_NAME: &str = "_default";
@@ -110,6 +110,7 @@ pub struct Profile {
     path: PathBuf,
     config: ProfileConfig,
     templates: templates::TemplateHandlebarsRegistry,
+    template_info: templates::TemplateInfo,
 }
 
 impl Object for Profile {
@@ -125,6 +126,7 @@ impl Profile {
             path: PathBuf::new(),
             config: ProfileConfig::new(),
             templates: templates::TemplateHandlebarsRegistry::new(),
+            template_info: templates::TemplateInfo::new(),
         }
     }
 
@@ -155,25 +157,41 @@ impl Profile {
         &self.templates
     }
 
+    /// Returns the parsed `template.toml` manifest for this profile's templates directory, or an
+    /// empty manifest if none was present.
+    pub fn template_info(&self) -> &templates::TemplateInfo {
+        &self.template_info
+    }
+
     /// Insert the contents of the files inside of the templates directory of the profile in the Handlebars registry.
     ///
-    /// Take note it will only get the contents of the top-level files in the templates folder.
+    /// The templates directory is walked recursively, so templates nested in subfolders are
+    /// registered under a namespaced name (e.g. `master/summary` for `master/summary.hbs`),
+    /// which also means a `master/_default.hbs` on disk overrides the built-in template of the
+    /// same name. `TemplateHandlebarsRegistry::new()` already seeds the registry with the
+    /// embedded default template set, so a profile with an empty or absent templates directory
+    /// still renders rather than erroring here.
     pub fn set_templates(&mut self) -> Result<()> {
-        if !self.has_templates() {
-            return Err(Error::InvalidProfileError(self.path.clone()));
+        let mut registry = templates::TemplateHandlebarsRegistry::new();
+
+        if self.has_templates() {
+            let template_info = templates::TemplateInfo::from_dir(self.templates_path())?;
+            let templates = TemplateGetter::get_templates_excluding(
+                self.templates_path(),
+                TEMPLATE_FILE_EXTENSION,
+                &template_info,
+            )?;
+            registry.register_vec(&templates)?;
+            self.template_info = template_info;
+
+            let helpers_path = self
+                .templates_path()
+                .join(PROFILE_TEMPLATE_HELPERS_DIR_NAME);
+            if helpers_path.is_dir() {
+                registry.register_script_helpers_from_dir(helpers_path)?;
+            }
         }
 
-        let mut registry = templates::TemplateHandlebarsRegistry::new();
-        // registering with the default templates
-        registry.register_template_string(PROFILE_NOTE_TEMPLATE_NAME, consts::NOTE_TEMPLATE)?;
-        registry.register_template_string(
-            PROFILE_MASTER_NOTE_TEMPLATE_NAME,
-            consts::MASTER_NOTE_TEMPLATE,
-        )?;
-
-        let templates =
-            TemplateGetter::get_templates(self.templates_path(), TEMPLATE_FILE_EXTENSION)?;
-        registry.register_vec(&templates)?;
         self.templates = registry;
 
         Ok(())
@@ -261,18 +279,44 @@ impl Profile {
         Ok(())
     }
 
-    /// Returns the command for compiling the notes.
+    /// Returns the command for compiling notes in the default (`tex`) format.
     /// By default, the compilation command is `latexmk -pdf`.
     ///
     /// If there's no valid value found from the key (i.e., invalid type), it will return the default command.
     pub fn compile_note_command(&self) -> String {
-        let PROFILE_DEFAULT_COMMAND = String::from("latexmk -pdf {{note}}");
-        match self.config.extra.get("command").as_ref() {
-            Some(value) => match value.is_str() {
-                true => value.as_str().unwrap().to_string(),
-                false => PROFILE_DEFAULT_COMMAND,
+        self.compile_note_command_for_format(templates::DEFAULT_TEMPLATE_FORMAT)
+    }
+
+    /// Returns the command for compiling a note whose template declared the given output
+    /// `format` (see `Template::from_path_relative`).
+    ///
+    /// The `command` key under `extra` may either be a plain string, applied to every format
+    /// (kept for backwards compatibility with profiles predating multi-format templates), or a
+    /// table keyed by format, e.g.:
+    ///
+    /// ```toml
+    /// [command]
+    /// md = "pandoc {{note}} -o {{note}}.pdf"
+    /// ```
+    ///
+    /// If neither yields a value, the `latexmk` default is used, but only for the `tex` format;
+    /// there is no sane default compiler for anything else.
+    pub fn compile_note_command_for_format<S: AsRef<str>>(
+        &self,
+        format: S,
+    ) -> String {
+        let format = format.as_ref();
+        let default_command = String::from("latexmk -pdf {{note}}");
+
+        match self.config.extra.get("command") {
+            Some(value) if value.is_str() => value.as_str().unwrap().to_string(),
+            Some(Value::Table(commands)) => match commands.get(format) {
+                Some(value) if value.is_str() => value.as_str().unwrap().to_string(),
+                _ if format == templates::DEFAULT_TEMPLATE_FORMAT => default_command,
+                _ => String::new(),
             },
-            None => PROFILE_DEFAULT_COMMAND,
+            _ if format == templates::DEFAULT_TEMPLATE_FORMAT => default_command,
+            _ => String::new(),
         }
     }
 }
@@ -457,4 +501,79 @@ mod tests {
         let test_path = PathBuf::from("./this/path/also/does/not/exists/lol");
         assert!(Profile::from(test_path).is_ok(), "Profile is not valid.");
     }
+
+    #[test]
+    fn profile_renders_the_embedded_default_template_without_a_templates_dir() -> Result<()> {
+        let (_tmp_dir, mut profile) = tmp_profile()?;
+        assert!(!profile.has_templates());
+
+        profile.set_templates()?;
+
+        assert!(profile
+            .template_registry()
+            .has_template(PROFILE_NOTE_TEMPLATE_NAME));
+        assert!(profile
+            .template_registry()
+            .render::<&str, toml::Value>(
+                PROFILE_NOTE_TEMPLATE_NAME,
+                toml::from_str("name = 'ME'").unwrap()
+            )
+            .is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn profile_renders_the_embedded_master_template_without_a_templates_dir() -> Result<()> {
+        let (_tmp_dir, mut profile) = tmp_profile()?;
+        assert!(!profile.has_templates());
+
+        profile.set_templates()?;
+
+        assert!(profile
+            .template_registry()
+            .has_template(PROFILE_MASTER_NOTE_TEMPLATE_NAME));
+
+        let rendered = profile
+            .template_registry()
+            .render::<&str, toml::Value>(
+                PROFILE_MASTER_NOTE_TEMPLATE_NAME,
+                toml::from_str("name = 'ME'").unwrap(),
+            )?;
+
+        // `{{standalone}}`/`\begin{{document}}` are Handlebars variable expressions, not
+        // literal doubled braces, so a correctly-rendered master template should keep the
+        // single braces LaTeX expects rather than rendering them away as empty lookups.
+        assert!(rendered.contains("{standalone}"));
+        assert!(rendered.contains("\\begin{document}"));
+        assert!(rendered.contains("\\end{document}"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn compile_note_command_falls_back_to_latexmk_only_for_tex() {
+        let mut commands = toml::value::Table::new();
+        commands.insert(
+            "md".to_string(),
+            Value::from("pandoc {{note}} -o {{note}}.pdf"),
+        );
+
+        let mut extra = HashMap::new();
+        extra.insert("command".to_string(), Value::Table(commands));
+
+        let mut builder = ProfileBuilder::new();
+        builder.extra_metadata(extra);
+        let profile = builder.build();
+
+        assert_eq!(
+            profile.compile_note_command_for_format("md"),
+            "pandoc {{note}} -o {{note}}.pdf"
+        );
+        assert_eq!(
+            profile.compile_note_command_for_format("tex"),
+            "latexmk -pdf {{note}}"
+        );
+        assert_eq!(profile.compile_note_command_for_format("typ"), "");
+    }
 }
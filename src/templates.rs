@@ -1,10 +1,14 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use globwalk;
 use handlebars;
-use serde;
+use rust_embed::RustEmbed;
+use serde::{self, Deserialize, Serialize};
+use toml;
 
+use crate::embedded::EmbeddedTemplates;
 use crate::error::Error;
 use crate::Result;
 
@@ -46,7 +50,16 @@ pub trait TemplateRegistry {
 }
 
 /// The template registry implemented with the `rust-handlebars` crate.
-pub struct TemplateHandlebarsRegistry(handlebars::Handlebars);
+///
+/// Templates are keyed in the underlying Handlebars registry by their bare name; the output
+/// format embedded in a template's filename (see `Template::from_path_relative`) is tracked
+/// alongside in `formats` rather than folded into the registry key, so `format_of` can answer
+/// "what should this template compile to" without callers having to parse the name back apart.
+pub struct TemplateHandlebarsRegistry {
+    registry: handlebars::Handlebars,
+    formats: HashMap<String, String>,
+    defaults: HashMap<String, toml::Value>,
+}
 
 impl TemplateRegistry for TemplateHandlebarsRegistry {
     /// Registers a template in the registry.
@@ -55,9 +68,14 @@ impl TemplateRegistry for TemplateHandlebarsRegistry {
         &mut self,
         template: &Template,
     ) -> Result<()> {
-        self.0
+        self.registry
             .register_template_string(&template.name, &template.s)
-            .map_err(Error::HandlebarsTemplateError)
+            .map_err(Error::HandlebarsTemplateError)?;
+        self.formats
+            .insert(template.name.clone(), template.format.clone());
+        self.set_defaults(&template.name, template.defaults.clone());
+
+        Ok(())
     }
 
     fn unregister<S>(
@@ -67,7 +85,9 @@ impl TemplateRegistry for TemplateHandlebarsRegistry {
     where
         S: AsRef<str>,
     {
-        self.0.unregister_template(template_name.as_ref());
+        self.registry.unregister_template(template_name.as_ref());
+        self.formats.remove(template_name.as_ref());
+        self.defaults.remove(template_name.as_ref());
 
         Ok(())
     }
@@ -79,7 +99,7 @@ impl TemplateRegistry for TemplateHandlebarsRegistry {
     where
         S: AsRef<str>,
     {
-        self.0.has_template(name.as_ref())
+        self.registry.has_template(name.as_ref())
     }
 
     fn render<S, V>(
@@ -91,24 +111,147 @@ impl TemplateRegistry for TemplateHandlebarsRegistry {
         S: AsRef<str>,
         V: serde::Serialize,
     {
-        self.0
-            .render(template_name.as_ref(), &value)
-            .map_err(Error::HandlebarsRenderError)
+        let name = template_name.as_ref();
+
+        match self.defaults.get(name) {
+            Some(defaults) => {
+                let mut context = defaults.clone();
+                let value = toml::Value::try_from(&value).map_err(Error::TomlValueError)?;
+                merge_toml_values(&mut context, &value);
+
+                self.registry
+                    .render(name, &context)
+                    .map_err(Error::HandlebarsRenderError)
+            }
+            None => self
+                .registry
+                .render(name, &value)
+                .map_err(Error::HandlebarsRenderError),
+        }
+    }
+}
+
+/// Deep-merges `overlay` into `base`, with `overlay`'s values winning on conflicts, except when
+/// both sides hold a table for the same key, in which case the tables are merged recursively
+/// instead of one replacing the other outright.
+///
+/// This mirrors `lanoma_lib::config::SubjectConfig::merge_values`, kept separate here since this
+/// crate doesn't depend on the library crate.
+fn merge_toml_values(
+    base: &mut toml::Value,
+    overlay: &toml::Value,
+) {
+    match (base, overlay) {
+        (toml::Value::Table(base_table), toml::Value::Table(overlay_table)) => {
+            for (key, overlay_value) in overlay_table {
+                match base_table.get_mut(key) {
+                    Some(base_value) => merge_toml_values(base_value, overlay_value),
+                    None => {
+                        base_table.insert(key.clone(), overlay_value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => {
+            *base = overlay.clone();
+        }
     }
 }
 
 impl TemplateHandlebarsRegistry {
-    /// Creates a new instance of the registry.
+    /// Creates a new instance of the registry, seeded with the built-in template set embedded
+    /// into the binary (see `crate::embedded::EmbeddedTemplates`).
     pub fn new() -> Self {
         let mut renderer = handlebars::Handlebars::new();
         renderer.register_escape_fn(handlebars::no_escape);
 
-        Self(renderer)
+        let mut registry = Self {
+            registry: renderer,
+            formats: HashMap::new(),
+            defaults: HashMap::new(),
+        };
+        registry.seed_embedded_defaults();
+
+        registry
+    }
+
+    /// Registers every embedded `.hbs` asset as a fallback template, named the same way an
+    /// on-disk template would be (namespaced by directory, with a trailing format segment like
+    /// the `.md` in `_default.md.hbs` split off). A later `register`/`register_vec` call for a
+    /// disk template of the same name simply overwrites these.
+    fn seed_embedded_defaults(&mut self) {
+        for file in EmbeddedTemplates::iter() {
+            let path = file.as_ref();
+            if !path.ends_with(".hbs") {
+                continue;
+            }
+
+            let asset = match EmbeddedTemplates::get(path) {
+                Some(asset) => asset,
+                None => continue,
+            };
+            let contents = match std::str::from_utf8(asset.data.as_ref()) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+
+            let defaults_path = format!("{}.toml", path.trim_end_matches(".hbs"));
+            let defaults = EmbeddedTemplates::get(&defaults_path).and_then(|asset| {
+                std::str::from_utf8(asset.data.as_ref())
+                    .ok()
+                    .and_then(|contents| toml::from_str(contents).ok())
+            });
+
+            if let Ok(template) = Template::from_embedded(path, contents, defaults) {
+                if self
+                    .registry
+                    .register_template_string(&template.name, &template.s)
+                    .is_ok()
+                {
+                    self.formats
+                        .insert(template.name.clone(), template.format.clone());
+                    self.set_defaults(&template.name, template.defaults.clone());
+                }
+            }
+        }
     }
 
     /// Returns the wrapped template engine as a reference.
     pub fn registry(&self) -> &handlebars::Handlebars {
-        &self.0
+        &self.registry
+    }
+
+    /// Returns the output format a registered template declared (e.g. `md`, `typ`), or `None` if
+    /// `name` isn't registered.
+    pub fn format_of<S: AsRef<str>>(
+        &self,
+        name: S,
+    ) -> Option<&str> {
+        self.formats.get(name.as_ref()).map(|format| format.as_str())
+    }
+
+    /// Returns the default render-context data a registered template carries (from its
+    /// companion `.toml` file), or `None` if `name` isn't registered or has no defaults.
+    pub fn defaults_of<S: AsRef<str>>(
+        &self,
+        name: S,
+    ) -> Option<&toml::Value> {
+        self.defaults.get(name.as_ref())
+    }
+
+    fn set_defaults(
+        &mut self,
+        name: &str,
+        defaults: Option<toml::Value>,
+    ) {
+        match defaults {
+            Some(defaults) => {
+                self.defaults.insert(name.to_string(), defaults);
+            }
+            None => {
+                self.defaults.remove(name);
+            }
+        }
     }
 
     /// Register a vector of template.
@@ -120,10 +263,13 @@ impl TemplateHandlebarsRegistry {
         let mut registered_templates = vec![];
         for template in templates.iter() {
             if self
-                .0
+                .registry
                 .register_template_string(&template.name, &template.s)
                 .is_ok()
             {
+                self.formats
+                    .insert(template.name.clone(), template.format.clone());
+                self.set_defaults(&template.name, template.defaults.clone());
                 registered_templates.push(template);
             }
         }
@@ -142,39 +288,243 @@ impl TemplateHandlebarsRegistry {
         N: AsRef<str>,
         S: AsRef<str>,
     {
-        self.0
+        self.registry
             .register_template_string(name.as_ref(), s.as_ref())
             .map_err(Error::HandlebarsTemplateError)
     }
+
+    /// Registers a Rust helper function under `name`, making it callable from every template in
+    /// the registry (e.g. `{{slugify title}}`).
+    pub fn register_helper<S>(
+        &mut self,
+        name: S,
+        helper: Box<dyn handlebars::HelperDef + Send + Sync>,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+    {
+        self.registry.register_helper(name.as_ref(), helper);
+
+        Ok(())
+    }
+
+    /// Registers every `*.rhai` script in `dir` as a helper named after its file stem, so users
+    /// can write a helper like `slugify` or `upper` once and call it from every note/subject
+    /// template instead of only the built-in Handlebars helpers.
+    ///
+    /// This is opt-in: callers that don't keep a `helpers` folder alongside their templates
+    /// never pay for the scan.
+    pub fn register_script_helpers_from_dir<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+    ) -> Result<()> {
+        let pattern = vec!["*.rhai".to_string()];
+        let scripts = globwalk::GlobWalkerBuilder::from_patterns(&dir, &pattern)
+            .build()
+            .map_err(Error::GlobParsingError)?;
+
+        for script in scripts {
+            if let Ok(script) = script {
+                let name = match script.path().file_stem() {
+                    Some(v) => v.to_string_lossy().to_string(),
+                    None => continue,
+                };
+
+                self.registry
+                    .register_script_helper(&name, script.path())
+                    .map_err(Error::HandlebarsScriptError)?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
+/// The output format assumed for a template that doesn't embed one in its filename.
+pub const DEFAULT_TEMPLATE_FORMAT: &str = "tex";
+
 /// A template is a Handlebars string to be rendered.
 /// This is specifically use in creating notes and other files that may need templating.
 pub struct Template {
     name: String,
+    format: String,
     s: String,
+    defaults: Option<toml::Value>,
 }
 
 impl Template {
     pub fn new() -> Self {
         Self {
             name: String::new(),
+            format: DEFAULT_TEMPLATE_FORMAT.to_string(),
             s: String::new(),
+            defaults: None,
+        }
+    }
+
+    /// Returns the template's registry name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the output format the template declared in its filename (e.g. `md`, `typ`), or
+    /// `DEFAULT_TEMPLATE_FORMAT` if it didn't declare one.
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// Returns the default render-context data loaded from the template's companion `.toml`
+    /// file, if one exists alongside it.
+    pub fn defaults(&self) -> Option<&toml::Value> {
+        self.defaults.as_ref()
+    }
+
+    /// Loads the data-binding file sitting next to `hbs_path`, e.g. `_default.md.hbs` pairs with
+    /// `_default.md.toml`, used to seed a template's render context with defaults the caller
+    /// doesn't have to supply every time (author name, boilerplate sections, and the like).
+    ///
+    /// Returns `None`, rather than an error, when no such file exists since most templates won't
+    /// have one.
+    fn load_defaults(hbs_path: &Path) -> Result<Option<toml::Value>> {
+        let defaults_path = hbs_path.with_extension("toml");
+        if !defaults_path.is_file() {
+            return Ok(None);
         }
+
+        let contents = fs::read_to_string(&defaults_path).map_err(Error::IoError)?;
+        let value: toml::Value = toml::from_str(&contents).map_err(Error::TomlValueError)?;
+
+        Ok(Some(value))
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
         let s = fs::read_to_string(&path).map_err(Error::IoError)?;
+        let defaults = Self::load_defaults(path)?;
+
+        let stem = match path.file_stem() {
+            Some(v) => v.to_string_lossy().to_string(),
+            None => return Err(Error::ValueError),
+        };
+        let (name, format) = Self::split_format(&stem);
+
+        Ok(Self { name, format, s, defaults })
+    }
+
+    /// Builds a template from an asset embedded at compile time (see
+    /// `crate::embedded::EmbeddedTemplates`), named the same way `from_path_relative` would be:
+    /// `relative_path` stripped of its `.hbs` suffix, with a trailing format segment split off.
+    fn from_embedded(
+        relative_path: &str,
+        contents: &str,
+        defaults: Option<toml::Value>,
+    ) -> Result<Self> {
+        let stem = relative_path.trim_end_matches(".hbs");
+        let (name, format) = Self::split_format(stem);
 
         Ok(Self {
-            name: match path.file_stem() {
-                Some(v) => v.to_string_lossy().to_string(),
-                None => return Err(Error::ValueError),
-            },
-            s,
+            name,
+            format,
+            s: contents.to_string(),
+            defaults,
         })
     }
+
+    /// Reads the template at `path` and names it after its path *relative to `root`*, with the
+    /// `.hbs` extension stripped, e.g. `<root>/master/summary.hbs` becomes `master/summary`.
+    ///
+    /// This is what lets templates be organized into namespaced subfolders instead of living
+    /// flat in the templates directory. A further extension segment on what's left, e.g. the
+    /// `.md` in `_default.md.hbs`, is treated as the template's declared output format rather
+    /// than part of the name; see `split_format`.
+    pub fn from_path_relative<P: AsRef<Path>, Q: AsRef<Path>>(
+        root: P,
+        path: Q,
+    ) -> Result<Self> {
+        let root = root.as_ref();
+        let path = path.as_ref();
+        let s = fs::read_to_string(&path).map_err(Error::IoError)?;
+        let defaults = Self::load_defaults(path)?;
+
+        let relative_stem = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .with_extension("")
+            .to_string_lossy()
+            .to_string();
+        let (name, format) = Self::split_format(&relative_stem);
+
+        Ok(Self { name, format, s, defaults })
+    }
+
+    /// Splits an `.hbs`-stripped template stem like `master/summary.md` into its registry name
+    /// (`master/summary`) and declared output format (`md`). A stem with no further extension,
+    /// e.g. `_default`, keeps its full name and defaults to `DEFAULT_TEMPLATE_FORMAT`.
+    fn split_format(stem: &str) -> (String, String) {
+        match stem.rsplit_once('.') {
+            Some((name, format)) => (name.to_string(), format.to_string()),
+            None => (stem.to_string(), DEFAULT_TEMPLATE_FORMAT.to_string()),
+        }
+    }
+}
+
+/// The name of the optional manifest describing a templates directory, read by
+/// `TemplateInfo::from_dir`.
+pub const TEMPLATE_INFO_FILENAME: &str = "template.toml";
+
+/// An optional manifest (`template.toml`) sitting alongside a profile's template files,
+/// describing the template package itself rather than any single template.
+///
+/// `excluded_files` lets a template author ship helper partials, assets, or scratch files in the
+/// templates directory without having them registered (and rendered) as notes/master note
+/// templates in their own right.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TemplateInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub website: Option<String>,
+
+    #[serde(default)]
+    pub excluded_files: Vec<String>,
+}
+
+impl TemplateInfo {
+    /// Creates an empty manifest, equivalent to there being no `template.toml` at all.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the manifest at `dir`/`template.toml`, returning an empty manifest if it doesn't
+    /// exist.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let manifest_path = dir.as_ref().join(TEMPLATE_INFO_FILENAME);
+        if !manifest_path.is_file() {
+            return Ok(Self::new());
+        }
+
+        let contents = fs::read_to_string(&manifest_path).map_err(Error::IoError)?;
+
+        toml::from_str(&contents).map_err(Error::TomlValueError)
+    }
+
+    /// Resolves `excluded_files` (globs relative to `root`) into the absolute paths they match.
+    pub fn excluded_paths<P: AsRef<Path>>(
+        &self,
+        root: P,
+    ) -> HashSet<PathBuf> {
+        if self.excluded_files.is_empty() {
+            return HashSet::new();
+        }
+
+        match globwalk::GlobWalkerBuilder::from_patterns(root, &self.excluded_files).build() {
+            Ok(walker) => walker
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path().to_path_buf())
+                .collect(),
+            Err(_e) => HashSet::new(),
+        }
+    }
 }
 
 /// A template builder.
@@ -182,11 +532,27 @@ impl Template {
 pub struct TemplateGetter;
 
 impl TemplateGetter {
-    /// Get a bunch of templates.
+    /// Recursively walks `path`, registering every file matching `glob` as a template named
+    /// after its path relative to `path` (see `Template::from_path_relative`), so templates in
+    /// subfolders keep a namespaced name like `master/summary` instead of colliding on their
+    /// bare file stem. Hidden files and directories (dot-prefixed) are skipped.
     pub fn get_templates<P, S>(
         path: P,
         glob: S,
     ) -> Result<Vec<Template>>
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        Self::get_templates_excluding(path, glob, &TemplateInfo::default())
+    }
+
+    /// Like `get_templates`, but also skips any file matched by `info`'s `excluded_files`.
+    pub fn get_templates_excluding<P, S>(
+        path: P,
+        glob: S,
+        info: &TemplateInfo,
+    ) -> Result<Vec<Template>>
     where
         P: AsRef<Path>,
         S: AsRef<str>,
@@ -195,12 +561,19 @@ impl TemplateGetter {
         let glob = glob.as_ref();
         let mut templates: Vec<Template> = vec![];
 
-        let tex_files = globwalk::GlobWalkerBuilder::new(path, glob)
+        let excluded = info.excluded_paths(path);
+
+        let pattern = vec![format!("**/{}", glob)];
+        let tex_files = globwalk::GlobWalkerBuilder::from_patterns(path, &pattern)
             .build()
             .map_err(Error::GlobParsingError)?;
         for tex_file in tex_files {
             if let Ok(file) = tex_file {
-                match Template::from_path(file.path()) {
+                if Self::is_hidden(file.path(), path) || excluded.contains(file.path()) {
+                    continue;
+                }
+
+                match Template::from_path_relative(path, file.path()) {
                     Ok(v) => templates.push(v),
                     Err(_e) => continue,
                 }
@@ -209,6 +582,24 @@ impl TemplateGetter {
 
         Ok(templates)
     }
+
+    /// Checks whether any path component of `entry_path`, relative to `root`, is dot-prefixed.
+    fn is_hidden(
+        entry_path: &Path,
+        root: &Path,
+    ) -> bool {
+        entry_path
+            .strip_prefix(root)
+            .unwrap_or(entry_path)
+            .components()
+            .any(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .map(|name| name.starts_with('.'))
+                    .unwrap_or(false)
+            })
+    }
 }
 
 #[cfg(test)]
@@ -236,4 +627,109 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn nested_templates_keep_a_namespaced_name() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new().map_err(Error::IoError)?;
+        let nested_dir = tmp_dir.path().join("master");
+        fs::create_dir(&nested_dir).map_err(Error::IoError)?;
+
+        for (dir, file) in &[
+            (tmp_dir.path(), "_default.tex"),
+            (nested_dir.as_path(), "summary.tex"),
+        ] {
+            let mut file_handle = fs::File::create(dir.join(file)).map_err(Error::IoError)?;
+            file_handle
+                .write(consts::NOTE_TEMPLATE.as_bytes())
+                .map_err(Error::IoError)?;
+        }
+
+        let template_files = TemplateGetter::get_templates(tmp_dir.path(), "*.tex")?;
+        let names: Vec<&str> = template_files.iter().map(|t| t.name.as_str()).collect();
+
+        assert_eq!(template_files.len(), 2);
+        assert!(names.contains(&"_default"));
+        assert!(names.contains(&"master/summary"));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn format_is_parsed_from_the_filename_and_defaults_to_tex() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new().map_err(Error::IoError)?;
+        for file in &["_default.hbs", "_default.md.hbs", "_default.typ.hbs"] {
+            let mut file_handle =
+                fs::File::create(tmp_dir.path().join(file)).map_err(Error::IoError)?;
+            file_handle
+                .write(consts::NOTE_TEMPLATE.as_bytes())
+                .map_err(Error::IoError)?;
+        }
+
+        let template_files = TemplateGetter::get_templates(tmp_dir.path(), "*.hbs")?;
+
+        assert_eq!(template_files.len(), 3);
+        assert!(template_files.iter().all(|t| t.name == "_default"));
+
+        let formats: Vec<&str> = template_files.iter().map(|t| t.format()).collect();
+        assert!(formats.contains(&DEFAULT_TEMPLATE_FORMAT));
+        assert!(formats.contains(&"md"));
+        assert!(formats.contains(&"typ"));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn excluded_files_are_skipped_during_registration() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new().map_err(Error::IoError)?;
+        for file in &["a.tex", "_partial.tex"] {
+            let mut file_handle =
+                fs::File::create(tmp_dir.path().join(file)).map_err(Error::IoError)?;
+            file_handle
+                .write(consts::NOTE_TEMPLATE.as_bytes())
+                .map_err(Error::IoError)?;
+        }
+
+        let mut info = TemplateInfo::new();
+        info.excluded_files = vec!["_partial.tex".to_string()];
+
+        let template_files =
+            TemplateGetter::get_templates_excluding(tmp_dir.path(), "*.tex", &info)?;
+
+        assert_eq!(template_files.len(), 1);
+        assert_eq!(template_files[0].name, "a");
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn companion_toml_defaults_are_deep_merged_under_caller_values() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new().map_err(Error::IoError)?;
+
+        let mut hbs_file =
+            fs::File::create(tmp_dir.path().join("_default.hbs")).map_err(Error::IoError)?;
+        hbs_file
+            .write("{{author}} - {{title}}".as_bytes())
+            .map_err(Error::IoError)?;
+
+        let mut toml_file =
+            fs::File::create(tmp_dir.path().join("_default.toml")).map_err(Error::IoError)?;
+        toml_file
+            .write(b"author = \"Anonymous\"\ntitle = \"Untitled\"\n")
+            .map_err(Error::IoError)?;
+
+        let template_files = TemplateGetter::get_templates(tmp_dir.path(), "*.hbs")?;
+        assert_eq!(template_files.len(), 1);
+        assert!(template_files[0].defaults().is_some());
+
+        let mut registry = TemplateHandlebarsRegistry::new();
+        registry.register(&template_files[0])?;
+
+        let mut caller_values = HashMap::new();
+        caller_values.insert("title", "My Note");
+
+        let rendered = registry.render("_default", &caller_values)?;
+        assert_eq!(rendered, "Anonymous - My Note");
+
+        Ok(())
+    }
 }